@@ -1,46 +1,494 @@
 use fourier_svg::{
-    export_to_draw_data, load_fourier_export, DrawData, ExportVisualizer, FourierConfig,
-    FourierSource, GIFVisualizer, HTMLVisualizer, Visualizer,
+    export_to_draw_data, load_fourier_export, APNGVisualizer, AnimatedSVGVisualizer, Animation,
+    ArrayContent, ArrayFormat, ArrayVisualizer, CodeLanguage, CodeVisualizer, DrawData,
+    ExportVisualizer, FourierConfig, FourierSource, Framing, GIFVisualizer, HTMLVisualizer,
+    HtmlOptions, MathFormat, MathVisualizer, MorphOptions, MorphTarget, PNGSequenceVisualizer,
+    PlotterFormat, PlotterVisualizer, RenderSummary, SVGVisualizer, TerminalVisualizer, Theme,
+    Visualizer, VisualizerError, WebPVisualizer, Y4MVisualizer,
 };
 
-use clap::Parser;
+use std::fmt::Display;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand};
+
+mod similarity;
+mod symmetry;
+mod term;
+mod tui;
 
 /// Draw a path in SVG format using Fourier Transform
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author = "Inoki <veyx.shaw@gmail.com>", version = "1.0.0", about)]
 #[command(arg_required_else_help = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Draw an SVG path in string
-    #[arg(short = 'p', long = "path")]
+    #[arg(short = 'p', long = "path", global = true)]
     svg_path: Option<String>,
 
     /// Draw the first SVG path in file
-    #[arg(short = 'f', long = "file")]
+    #[arg(short = 'f', long = "file", global = true)]
     svg_file: Option<String>,
 
     /// Load from exported Fourier data JSON file
-    #[arg(short = 'i', long = "input")]
+    #[arg(short = 'i', long = "input", global = true)]
     input_file: Option<String>,
 
     /// Use how many sample points to draw the path
-    #[arg(short = 's', long = "sample", default_value = "10240")]
+    #[arg(short = 's', long = "sample", default_value = "10240", global = true)]
     num_sample: usize,
 
     /// Use how many waves to draw the path
-    #[arg(short = 'w', long = "wave", default_value = "201")]
+    #[arg(short = 'w', long = "wave", default_value = "201", global = true)]
     num_wave: usize,
 
-    /// Rendering backend: html, gif, export
+    /// Rendering backend: html, svg, svg-anim, gif, apng, webp, png-seq, y4m, term, code, math, plot, csv, npy, export
     #[arg(short = 'b', long = "backend", default_value = "html")]
     backend: String,
 
-    /// Output file name (without extension)
-    #[arg(short = 'o', long = "output", default_value = "output")]
+    /// Output file name (without extension, a directory for png-seq), "-" for stdout
+    #[arg(short = 'o', long = "output", default_value = "output", global = true)]
     output: String,
 
-    /// Number of frames for GIF output
+    /// Number of frames for animated output (gif, apng, webp, png-seq, y4m, term)
     #[arg(long = "frames", default_value = "100")]
     frames: usize,
+
+    /// Number of full drawing periods in the animation
+    #[arg(long = "cycles", default_value = "1")]
+    cycles: usize,
+
+    /// Extra frames holding the finished drawing at the end of the loop
+    #[arg(long = "hold-frames", default_value = "0")]
+    hold_frames: usize,
+
+    /// Length of the trail as a fraction of a period (html and animated outputs)
+    #[arg(long = "trail", default_value = "0.8")]
+    trail: f32,
+
+    /// Frame rate of animated output, 50 by default
+    #[arg(long = "fps")]
+    fps: Option<u32>,
+
+    /// Frame or canvas size "WIDTHxHEIGHT" (html and animated outputs)
+    #[arg(long = "size", default_value = "800x600", value_parser = parse_dimensions::<u32>)]
+    size: (u32, u32),
+
+    /// Morph into the first path of this SVG file or exported JSON (html and animated outputs)
+    #[arg(long = "morph-to")]
+    morph_to: Option<String>,
+
+    /// Duration of the morph in seconds
+    #[arg(long = "morph-duration", default_value = "2.0")]
+    morph_duration: f32,
+
+    /// Align the start point of the morph target with the source shape
+    #[arg(long = "align-start")]
+    align_start: bool,
+
+    /// Margin in pixels kept around the drawing (html and animated outputs)
+    #[arg(long = "padding", default_value = "20")]
+    padding: f32,
+
+    /// Fixed scale in pixels per SVG unit instead of fitting the frame (html and animated outputs)
+    #[arg(long = "scale")]
+    scale: Option<f32>,
+
+    /// Pixel position "x,y" of the SVG origin instead of centering the drawing (html and animated outputs)
+    #[arg(long = "origin", value_parser = parse_point)]
+    origin: Option<(f32, f32)>,
+
+    /// Theme preset (classic, dark, blueprint, rainbow, transparent) or JSON/TOML theme file (html, svg and animated outputs)
+    #[arg(long = "theme", default_value = "classic", global = true)]
+    theme: String,
+
+    /// Seconds taken to draw one period (html, svg-anim) [default: 8.3 for html, 8 for svg-anim]
+    #[arg(long = "duration")]
+    duration: Option<f32>,
+
+    /// Page title (html)
+    #[arg(long = "title")]
+    title: Option<String>,
+
+    /// Add player controls: pause, speed, time, circle count, toggles, JSON download (html)
+    #[arg(long = "controls")]
+    controls: bool,
+
+    /// Custom page template with {{script}}, {{data}}, {{title}}... placeholders (html)
+    #[arg(long = "template")]
+    template: Option<String>,
+
+    /// Characters drawing the terminal preview: braille or half-block (term)
+    #[arg(long = "term-mode", default_value = "braille")]
+    term_mode: String,
+
+    /// Draw the terminal preview without colors (term)
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Language of the generated source: rust, js, python or glsl (code)
+    #[arg(long = "language", default_value = "rust", value_parser = parse_language)]
+    language: CodeLanguage,
+
+    /// Equations written: latex, latex-complex or desmos (math)
+    #[arg(long = "math-format", default_value = "latex", value_parser = parse_math_format)]
+    math_format: MathFormat,
+
+    /// Write the curve for y pointing up instead of down as in SVG (code, math)
+    #[arg(long = "y-up")]
+    y_up: bool,
+
+    /// Decimals of the written numbers (code, math) [default: 4 for code, 2 for math]
+    #[arg(long = "precision")]
+    precision: Option<usize>,
+
+    /// Leave out terms with a radius below this fraction of the largest one (math)
+    #[arg(long = "tolerance", default_value = "0.001")]
+    tolerance: f32,
+
+    /// Plotter commands written: gcode or hpgl (plot)
+    #[arg(long = "plot-format", default_value = "gcode", value_parser = parse_plot_format)]
+    plot_format: PlotterFormat,
+
+    /// Box "WIDTHxHEIGHT" in millimetres the drawing is fitted in (plot)
+    #[arg(long = "plot-size", default_value = "180x180", value_parser = parse_dimensions::<f32>)]
+    plot_size: (f32, f32),
+
+    /// Lower left corner "x,y" of the box on the bed in millimetres (plot)
+    #[arg(long = "plot-origin", default_value = "10,10", value_parser = parse_point)]
+    plot_origin: (f32, f32),
+
+    /// Reachable area "WIDTHxHEIGHT" of the plotter in millimetres (plot)
+    #[arg(long = "bed", default_value = "200x200", value_parser = parse_dimensions::<f32>)]
+    bed: (f32, f32),
+
+    /// Drawing speed in mm/min, pen down (plot)
+    #[arg(long = "feed-rate", default_value = "1500")]
+    feed_rate: f32,
+
+    /// Travel speed in mm/min, pen up (plot, G-code only)
+    #[arg(long = "travel-rate", default_value = "3000")]
+    travel_rate: f32,
+
+    /// G-code lifting the pen, e.g. "M5" for a laser (plot)
+    #[arg(long = "pen-up", default_value = "G0 Z5")]
+    pen_up: String,
+
+    /// G-code lowering the pen, e.g. "M3 S1000" for a laser (plot)
+    #[arg(long = "pen-down", default_value = "G1 Z0")]
+    pen_down: String,
+
+    /// Draw every path of the SVG file, toured to keep pen-up moves short (plot)
+    #[arg(long = "all-paths")]
+    all_paths: bool,
+
+    /// Numbers written: coefficients (frequency, re, im, radius, angle) or samples (x, y) (csv, npy)
+    #[arg(long = "array", default_value = "coefficients", value_parser = parse_array_content)]
+    array: ArrayContent,
+
+    /// Include the points the path was sampled at (export)
+    #[arg(long = "include-samples")]
+    include_samples: bool,
+
+    /// Circles drawing the reconstruction, several comma-separated counts making a grid (svg, svg-anim, code, math, plot)
+    #[arg(long = "terms", value_delimiter = ',')]
+    terms: Vec<usize>,
+
+    /// Show the original path under the reconstruction (svg)
+    #[arg(long = "overlay-original")]
+    overlay_original: bool,
+
+    /// Show the circles and arms at this time, in periods from 0 to 1 (svg)
+    #[arg(long = "circles-at")]
+    circles_at: Option<f32>,
+
+    /// Enforce a symmetry before rendering: a rotational order (e.g. 5) or "mirror"
+    #[arg(long = "enforce-symmetry")]
+    enforce_symmetry: Option<String>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Print how similar two shapes are (0 = same shape)
+    Compare {
+        /// First shape: SVG file, exported JSON or SVG path string
+        first: String,
+        /// Second shape: SVG file, exported JSON or SVG path string
+        second: String,
+        /// Number of frequency pairs in the shape descriptors
+        #[arg(long = "order", default_value = "16")]
+        order: usize,
+    },
+    /// Rank the SVG files of a directory by similarity to a query shape
+    Search {
+        /// Query shape: SVG file, exported JSON or SVG path string
+        query: String,
+        /// Directory containing the SVG files to rank
+        directory: String,
+        /// Show only the best matches
+        #[arg(long = "top", default_value = "10")]
+        top: usize,
+        /// Number of frequency pairs in the shape descriptors
+        #[arg(long = "order", default_value = "16")]
+        order: usize,
+    },
+    /// Report the rotational and mirror symmetries of a shape
+    Symmetry {
+        /// Shape: SVG file, exported JSON or SVG path string
+        shape: String,
+        /// Largest rotational order to test
+        #[arg(long = "max-order", default_value_t = fourier_svg::DEFAULT_MAX_SYMMETRY_ORDER)]
+        max_order: usize,
+        /// Smallest confidence (0-1) for a symmetry to be reported
+        #[arg(long = "threshold", default_value_t = fourier_svg::DEFAULT_SYMMETRY_THRESHOLD)]
+        threshold: f32,
+    },
+    /// Explore the coefficients in a full-screen terminal UI
+    ///
+    /// Exports take the backend options given before the subcommand, e.g.
+    /// `fourier-svg --size 400x300 --language glsl tui -f shape.svg`.
+    Tui,
+}
+
+/// Parse "WIDTHxHEIGHT" dimensions, a frame size in pixels or a plot size in millimetres
+fn parse_dimensions<T: FromStr>(value: &str) -> Result<(T, T), String>
+where
+    T::Err: Display,
+{
+    let (width, height) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected \"WIDTHxHEIGHT\", got \"{}\"", value))?;
+    let parse = |v: &str| v.trim().parse::<T>().map_err(|e| e.to_string());
+    Ok((parse(width)?, parse(height)?))
+}
+
+/// Parse an "x,y" pair
+fn parse_point(value: &str) -> Result<(f32, f32), String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected \"x,y\", got \"{}\"", value))?;
+    let parse = |v: &str| v.trim().parse::<f32>().map_err(|e| e.to_string());
+    Ok((parse(x)?, parse(y)?))
+}
+
+/// Parse a --language name
+fn parse_language(value: &str) -> Result<CodeLanguage, String> {
+    match value {
+        "rust" => Ok(CodeLanguage::Rust),
+        "js" | "javascript" => Ok(CodeLanguage::JavaScript),
+        "python" => Ok(CodeLanguage::Python),
+        "glsl" => Ok(CodeLanguage::Glsl),
+        _ => Err(format!(
+            "unknown language \"{}\", expected rust, js, python or glsl",
+            value
+        )),
+    }
+}
+
+/// Parse a --math-format name
+fn parse_math_format(value: &str) -> Result<MathFormat, String> {
+    match value {
+        "latex" => Ok(MathFormat::Latex),
+        "latex-complex" => Ok(MathFormat::LatexComplex),
+        "desmos" => Ok(MathFormat::Desmos),
+        _ => Err(format!(
+            "unknown format \"{}\", expected latex, latex-complex or desmos",
+            value
+        )),
+    }
+}
+
+/// Parse a --plot-format name
+fn parse_plot_format(value: &str) -> Result<PlotterFormat, String> {
+    match value {
+        "gcode" => Ok(PlotterFormat::GCode),
+        "hpgl" => Ok(PlotterFormat::Hpgl),
+        _ => Err(format!(
+            "unknown format \"{}\", expected gcode or hpgl",
+            value
+        )),
+    }
+}
+
+/// Parse an --array name
+fn parse_array_content(value: &str) -> Result<ArrayContent, String> {
+    match value {
+        "coefficients" => Ok(ArrayContent::Coefficients),
+        "samples" => Ok(ArrayContent::Samples),
+        _ => Err(format!(
+            "unknown array \"{}\", expected coefficients or samples",
+            value
+        )),
+    }
+}
+
+/// Framing of the html and gif outputs from the command line
+fn framing(args: &Args) -> Framing {
+    let mut framing = Framing::default().with_padding(args.padding);
+    if let Some(scale) = args.scale {
+        framing = framing.with_scale(scale);
+    }
+    if let Some((x, y)) = args.origin {
+        framing = framing.with_origin(x, y);
+    }
+    framing
+}
+
+/// Timeline and appearance of the animated outputs
+fn animation(args: &Args, theme: Theme, morph: Option<MorphTarget>) -> Animation {
+    let mut animation = Animation::default()
+        .with_dimensions(args.size.0, args.size.1)
+        .with_frames(args.frames)
+        .with_cycles(args.cycles)
+        .with_hold_frames(args.hold_frames)
+        .with_trail(args.trail)
+        .with_framing(framing(args))
+        .with_theme(theme);
+    if let Some(fps) = args.fps {
+        animation = animation.with_fps(fps);
+    }
+    if let Some(target) = morph {
+        animation = animation.with_morph(target);
+    }
+    animation
+}
+
+/// Canvas, player and page settings of the html output
+fn html_options(args: &Args, theme: Theme) -> Result<HtmlOptions, String> {
+    let mut options = HtmlOptions::default()
+        .with_dimensions(args.size.0, args.size.1)
+        .with_trail(args.trail)
+        .with_controls(args.controls)
+        .with_theme(theme);
+    if let Some(duration) = args.duration {
+        options = options.with_speed(1.0 / duration);
+    }
+    if let Some(title) = &args.title {
+        options = options.with_title(title.clone());
+    }
+    if let Some(template) = &args.template {
+        options = options.with_template_file(template)?;
+    }
+    Ok(options)
+}
+
+/// Static SVG of the reconstruction, sized from the viewBox of the input file
+fn svg_visualizer(args: &Args, theme: Theme) -> Result<SVGVisualizer, String> {
+    let mut visualizer = SVGVisualizer::new(format!("{}.svg", args.output))
+        .with_terms(args.terms.clone())
+        .with_theme(theme);
+    if let Some(svg_file) = &args.svg_file {
+        if let Some(view_box) = fourier_svg::extract_view_box_from_file(svg_file)? {
+            visualizer = visualizer.with_view_box(view_box);
+        }
+    }
+    if args.overlay_original {
+        let original = match (&args.svg_file, &args.svg_path) {
+            (Some(svg_file), _) => fourier_svg::extract_first_path_from_file(svg_file)?,
+            (None, Some(svg_path)) => svg_path.clone(),
+            (None, None) => return Err("--overlay-original needs -p or -f".to_string()),
+        };
+        visualizer = visualizer.with_original(original);
+    }
+    if let Some(t) = args.circles_at {
+        visualizer = visualizer.with_circles_at(t);
+    }
+    Ok(visualizer)
+}
+
+/// Interpret a shape argument: an exported JSON file, an SVG file or else an SVG path string
+fn source_from_arg(arg: &str) -> FourierSource<'_> {
+    if arg.ends_with(".json") {
+        FourierSource::FourierJson(arg)
+    } else if std::path::Path::new(arg).is_file() {
+        FourierSource::SvgFile(arg)
+    } else {
+        FourierSource::SvgPath(arg)
+    }
+}
+
+/// Load the morph target, from an exported JSON file or the first path of an SVG file
+fn load_morph_target(args: &Args, config: &FourierConfig) -> Result<Option<MorphTarget>, String> {
+    let Some(morph_to) = &args.morph_to else {
+        return Ok(None);
+    };
+
+    let data = fourier_svg::process_source(source_from_arg(morph_to), config)?;
+    let options = MorphOptions::new().with_start_point_alignment(args.align_start);
+
+    Ok(Some(
+        MorphTarget::new(data, args.morph_duration).with_options(options),
+    ))
+}
+
+/// Points a path was sampled at
+type Samples = Vec<(f32, f32)>;
+
+/// Get Fourier data from the appropriate source, with the symmetry enforced,
+/// and the points the path was sampled at when a backend writes them
+fn load_data(
+    args: &Args,
+    config: &FourierConfig,
+) -> Result<(Vec<DrawData>, Option<Samples>), String> {
+    let (data, samples) = if let Some(input_path) = &args.input_file {
+        let export = load_fourier_export(input_path)
+            .map_err(|e| format!("Failed to load Fourier data: {}", e))?;
+        eprintln!(
+            "Loaded Fourier data from {} ({} coefficients, {} samples)",
+            input_path, export.metadata.wave_count, export.metadata.sample_count
+        );
+        (export_to_draw_data(&export), export.samples)
+    } else {
+        let svg_path = match (&args.svg_file, &args.svg_path) {
+            (Some(svg_file), _) => fourier_svg::extract_first_path_from_file(svg_file)
+                .map_err(|e| format!("Error: {}", e))?,
+            (None, Some(svg_path)) => svg_path.clone(),
+            (None, None) => {
+                return Err("No SVG path provided. Use -p <path> or -f <file>.".to_string())
+            }
+        };
+        let samples = (args.include_samples || args.array == ArrayContent::Samples)
+            .then(|| fourier_svg::sample_svg_path(&svg_path, config));
+        (fourier_svg::process_svg_path(&svg_path, config), samples)
+    };
+
+    let data = match &args.enforce_symmetry {
+        Some(spec) => symmetry::enforce(spec, &data).map_err(|e| format!("Error: {}", e))?,
+        None => data,
+    };
+    Ok((data, samples))
+}
+
+/// Explore the coefficients in the terminal, exporting with the file backends
+fn run_tui(args: &Args, config: &FourierConfig) -> Result<(), String> {
+    let (data, samples) = load_data(args, config)?;
+    let theme = Theme::resolve(&args.theme)?;
+    // The terms chosen in the UI are exported as they are, not cut by --terms
+    let args = &Args {
+        terms: Vec::new(),
+        ..args.clone()
+    };
+    let export = |backend: &str, data: Vec<DrawData>| {
+        if args.output == "-" {
+            return Err("cannot export to stdout from the TUI, use -o".to_string());
+        }
+        visualizer(
+            args,
+            config,
+            backend,
+            theme.clone(),
+            None,
+            samples.as_deref(),
+        )?
+        .render(data)
+        .map(saved)
+        .map_err(|e| format!("Rendering failed: {}", e))
+    };
+    tui::run(data, theme.clone(), &FILE_BACKENDS, export)
 }
 
 fn main() {
@@ -48,67 +496,269 @@ fn main() {
 
     let config = FourierConfig::new(args.num_sample, args.num_wave);
 
-    // Get Fourier data from the appropriate source
-    let data: Vec<DrawData> = if let Some(input_path) = &args.input_file {
-        match load_fourier_export(input_path) {
-            Ok(export) => {
-                println!(
-                    "Loaded Fourier data from {} ({} coefficients, {} samples)",
-                    input_path, export.metadata.wave_count, export.metadata.sample_count
-                );
-                export_to_draw_data(&export)
-            }
-            Err(e) => {
-                eprintln!("Failed to load Fourier data: {}", e);
-                return;
-            }
+    if let Some(command) = &args.command {
+        let result = match command {
+            Command::Compare {
+                first,
+                second,
+                order,
+            } => similarity::compare(first, second, *order, &config),
+            Command::Search {
+                query,
+                directory,
+                top,
+                order,
+            } => similarity::search(query, directory, *top, *order, &config),
+            Command::Symmetry {
+                shape,
+                max_order,
+                threshold,
+            } => symmetry::report(shape, *max_order, *threshold, &config),
+            Command::Tui => run_tui(&args, &config),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
         }
-    } else {
-        let source = if let Some(svg_file) = &args.svg_file {
-            FourierSource::SvgFile(svg_file)
-        } else if let Some(svg_path) = &args.svg_path {
-            FourierSource::SvgPath(svg_path)
-        } else {
-            println!("No SVG path provided. Use -p <path> or -f <file>.");
+        return;
+    }
+
+    let (data, samples) = match load_data(&args, &config) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
             return;
-        };
+        }
+    };
 
-        match fourier_svg::process_source(source, &config) {
-            Ok(d) => d,
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                return;
-            }
+    let morph = match load_morph_target(&args, &config) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Failed to load morph target: {}", e);
+            return;
         }
     };
 
-    // Select visualizer based on backend
-    let success =
-        match args.backend.as_str() {
-            "html" => {
-                let visualizer = HTMLVisualizer::new(format!("{}.html", args.output));
-                visualizer.render(data)
+    let theme = match Theme::resolve(&args.theme) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    // The terminal preview plays until it is quit instead of writing a file
+    if args.backend == "term" {
+        let result = term::parse_mode(&args.term_mode).and_then(|mode| {
+            let visualizer = TerminalVisualizer::default()
+                .with_animation(animation(&args, theme, morph))
+                .with_mode(mode)
+                .with_color(!args.no_color);
+            term::preview(visualizer, &data)
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+        }
+        return;
+    }
+
+    let visualizer = match visualizer(
+        &args,
+        &config,
+        &args.backend,
+        theme,
+        morph,
+        samples.as_deref(),
+    ) {
+        Ok(visualizer) => visualizer,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    // Status goes to stderr so that "-o -" leaves stdout to the rendered data
+    match render(visualizer.as_ref(), data, args.output == "-") {
+        Ok(summary) => eprintln!("{}", saved(summary)),
+        Err(e) => eprintln!("Rendering failed: {}", e),
+    }
+}
+
+/// Visualizer of a file writing backend, set up from the command line.
+/// `samples` are the points the input path was sampled at, if loaded.
+fn visualizer(
+    args: &Args,
+    config: &FourierConfig,
+    backend: &str,
+    theme: Theme,
+    morph: Option<MorphTarget>,
+    samples: Option<&[(f32, f32)]>,
+) -> Result<Box<dyn Visualizer>, String> {
+    let visualizer: Box<dyn Visualizer> = match backend {
+        "html" => {
+            let mut visualizer = HTMLVisualizer::new(format!("{}.html", args.output))
+                .with_framing(framing(args))
+                .with_options(html_options(args, theme)?)
+                .with_metadata(args.svg_path.clone(), args.num_sample, args.num_wave);
+            if let Some(target) = morph {
+                visualizer = visualizer.with_morph(target);
             }
-            "gif" => {
-                let visualizer =
-                    GIFVisualizer::new(format!("{}.gif", args.output)).with_frames(args.frames);
-                visualizer.render(data)
+            Box::new(visualizer)
+        }
+        "svg" => Box::new(svg_visualizer(args, theme)?),
+        "svg-anim" => {
+            let mut visualizer = AnimatedSVGVisualizer::new(format!("{}.svg", args.output))
+                .with_dimensions(args.size.0, args.size.1)
+                .with_framing(framing(args))
+                .with_theme(theme);
+            if let Some(terms) = args.terms.first() {
+                visualizer = visualizer.with_terms(*terms);
             }
-            "export" => {
-                let visualizer = ExportVisualizer::new(format!("{}.json", args.output))
-                    .with_metadata(args.svg_path.clone(), args.num_sample, args.num_wave);
-                visualizer.render(data)
+            if let Some(duration) = args.duration {
+                visualizer = visualizer.with_duration(duration);
             }
-            _ => {
-                eprintln!(
-                    "Unknown backend: {}. Available options: html, gif, export",
-                    args.backend
-                );
-                false
+            Box::new(visualizer)
+        }
+        "gif" => Box::new(
+            GIFVisualizer::new(format!("{}.gif", args.output))
+                .with_animation(animation(args, theme, morph)),
+        ),
+        "apng" => Box::new(
+            APNGVisualizer::new(format!("{}.png", args.output))
+                .with_animation(animation(args, theme, morph)),
+        ),
+        "webp" => Box::new(
+            WebPVisualizer::new(format!("{}.webp", args.output))
+                .with_animation(animation(args, theme, morph)),
+        ),
+        "png-seq" => Box::new(
+            PNGSequenceVisualizer::new(args.output.clone())
+                .with_animation(animation(args, theme, morph)),
+        ),
+        "y4m" => Box::new(
+            Y4MVisualizer::new(format!("{}.y4m", args.output))
+                .with_animation(animation(args, theme, morph)),
+        ),
+        "code" => {
+            let mut visualizer = CodeVisualizer::new(format!(
+                "{}.{}",
+                args.output,
+                args.language.extension()
+            ))
+            .with_language(args.language)
+            .with_y_up(args.y_up);
+            if let Some(precision) = args.precision {
+                visualizer = visualizer.with_precision(precision);
             }
-        };
+            if let Some(terms) = args.terms.first() {
+                visualizer = visualizer.with_terms(*terms);
+            }
+            Box::new(visualizer)
+        }
+        "math" => {
+            let mut visualizer = MathVisualizer::new(format!(
+                "{}.{}",
+                args.output,
+                args.math_format.extension()
+            ))
+            .with_format(args.math_format)
+            .with_tolerance(args.tolerance)
+            .with_y_up(args.y_up);
+            if let Some(precision) = args.precision {
+                visualizer = visualizer.with_precision(precision);
+            }
+            if let Some(terms) = args.terms.first() {
+                visualizer = visualizer.with_terms(*terms);
+            }
+            Box::new(visualizer)
+        }
+        "plot" => {
+            let mut visualizer = PlotterVisualizer::new(format!(
+                "{}.{}",
+                args.output,
+                args.plot_format.extension()
+            ))
+            .with_format(args.plot_format)
+            .with_size(args.plot_size.0, args.plot_size.1)
+            .with_origin(args.plot_origin.0, args.plot_origin.1)
+            .with_bed(args.bed.0, args.bed.1)
+            .with_feed_rates(args.feed_rate, args.travel_rate)
+            .with_pen_commands(args.pen_up.clone(), args.pen_down.clone());
+            if let Some(terms) = args.terms.first() {
+                visualizer = visualizer.with_terms(*terms);
+            }
+            if args.all_paths {
+                let svg_file = args
+                    .svg_file
+                    .as_deref()
+                    .ok_or("--all-paths needs an SVG file, use -f <file>")?;
+                visualizer =
+                    visualizer.with_layers(fourier_svg::process_multiple_paths(svg_file, config)?);
+            }
+            Box::new(visualizer)
+        }
+        "csv" | "npy" => {
+            let format = match backend {
+                "csv" => ArrayFormat::Csv,
+                _ => ArrayFormat::Npy,
+            };
+            let mut visualizer =
+                ArrayVisualizer::new(format!("{}.{}", args.output, format.extension()))
+                    .with_format(format)
+                    .with_content(args.array);
+            if let Some(samples) = samples.filter(|_| args.array == ArrayContent::Samples) {
+                visualizer = visualizer.with_samples(samples.to_vec());
+            }
+            Box::new(visualizer)
+        }
+        "export" => {
+            let mut visualizer = ExportVisualizer::new(format!("{}.json", args.output))
+                .with_metadata(args.svg_path.clone(), args.num_sample, args.num_wave);
+            if let Some(samples) = samples.filter(|_| args.include_samples) {
+                visualizer = visualizer.with_samples(samples.to_vec());
+            }
+            Box::new(visualizer)
+        }
+        _ => {
+            return Err(format!(
+                "Unknown backend: {}. Available options: html, svg, svg-anim, gif, apng, webp, png-seq, y4m, term, code, math, plot, csv, npy, export",
+                backend
+            ))
+        }
+    };
+    Ok(visualizer)
+}
 
-    if !success {
-        eprintln!("Rendering failed!");
+/// Backends writing a file, as accepted by `visualizer`
+const FILE_BACKENDS: [&str; 14] = [
+    "html", "svg", "svg-anim", "gif", "apng", "webp", "png-seq", "y4m", "code", "math", "plot",
+    "csv", "npy", "export",
+];
+
+/// What a render produced, for the user
+fn saved(summary: RenderSummary) -> String {
+    let output = summary.output.unwrap_or_else(|| "stdout".to_string());
+    if summary.frames > 0 {
+        format!(
+            "Saved {} ({} bytes, {} frames)",
+            output, summary.bytes, summary.frames
+        )
+    } else {
+        format!("Saved {} ({} bytes)", output, summary.bytes)
     }
 }
+
+/// Render into the visualizer's file, or into stdout
+fn render(
+    visualizer: &dyn Visualizer,
+    data: Vec<DrawData>,
+    to_stdout: bool,
+) -> Result<RenderSummary, VisualizerError> {
+    if !to_stdout {
+        return visualizer.render(data);
+    }
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    let summary = visualizer.render_to(data, &mut writer)?;
+    writer.flush()?;
+    Ok(summary)
+}
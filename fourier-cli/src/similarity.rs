@@ -0,0 +1,68 @@
+//! `compare` and `search` subcommands, built on Fourier descriptors
+
+use std::fs;
+
+use fourier_svg::{FourierConfig, FourierDescriptor, FourierSource};
+
+use crate::source_from_arg;
+
+fn descriptor_of(
+    source: FourierSource,
+    order: usize,
+    config: &FourierConfig,
+) -> Result<FourierDescriptor, String> {
+    let data = fourier_svg::process_source(source, config)?;
+    Ok(FourierDescriptor::from_draw_data(&data, order))
+}
+
+/// Print the descriptor distance between two shapes
+pub fn compare(
+    first: &str,
+    second: &str,
+    order: usize,
+    config: &FourierConfig,
+) -> Result<(), String> {
+    let a = descriptor_of(source_from_arg(first), order, config)?;
+    let b = descriptor_of(source_from_arg(second), order, config)?;
+
+    println!("{:.6}", a.distance(&b));
+    Ok(())
+}
+
+/// Print the `top` SVG files of `directory` closest to the query shape
+pub fn search(
+    query: &str,
+    directory: &str,
+    top: usize,
+    order: usize,
+    config: &FourierConfig,
+) -> Result<(), String> {
+    let query = descriptor_of(source_from_arg(query), order, config)?;
+
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read directory {}: {}", directory, e))?;
+
+    let mut ranking = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_svg = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+        if !is_svg {
+            continue;
+        }
+
+        let file = path.to_string_lossy().to_string();
+        match descriptor_of(FourierSource::SvgFile(&file), order, config) {
+            Ok(descriptor) => ranking.push((query.distance(&descriptor), file)),
+            Err(e) => eprintln!("Skipping {}: {}", file, e),
+        }
+    }
+
+    ranking.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (distance, file) in ranking.iter().take(top) {
+        println!("{:.6}  {}", distance, file);
+    }
+    Ok(())
+}
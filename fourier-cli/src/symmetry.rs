@@ -0,0 +1,54 @@
+//! `symmetry` subcommand and the `--enforce-symmetry` filter
+
+use fourier_svg::{
+    detect_symmetries, enforce_symmetry, mirror_symmetry, DrawData, FourierConfig, Symmetry,
+};
+
+use crate::source_from_arg;
+
+/// Print the symmetries detected in a shape
+pub fn report(
+    shape: &str,
+    max_order: usize,
+    threshold: f32,
+    config: &FourierConfig,
+) -> Result<(), String> {
+    let data = fourier_svg::process_source(source_from_arg(shape), config)?;
+    let detected = detect_symmetries(&data, max_order, threshold);
+
+    if detected.is_empty() {
+        println!("No symmetry found above {:.2} confidence", threshold);
+    }
+    for d in detected {
+        match d.symmetry {
+            Symmetry::Rotational { order } => {
+                println!("{:.3}  {}-fold rotational", d.confidence, order)
+            }
+            Symmetry::Mirror { axis_angle, .. } => println!(
+                "{:.3}  mirror, axis at {:.1} degrees",
+                d.confidence,
+                axis_angle.to_degrees()
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Apply an `--enforce-symmetry` spec: a rotational order or "mirror"
+pub fn enforce(spec: &str, data: &[DrawData]) -> Result<Vec<DrawData>, String> {
+    let symmetry = if spec.eq_ignore_ascii_case("mirror") {
+        mirror_symmetry(data).symmetry
+    } else {
+        match spec.parse::<usize>() {
+            Ok(order) if order >= 2 => Symmetry::Rotational { order },
+            _ => {
+                return Err(format!(
+                "Invalid symmetry '{}': expected a rotational order of at least 2 or \"mirror\"",
+                spec
+            ))
+            }
+        }
+    };
+
+    Ok(enforce_symmetry(data, &symmetry))
+}
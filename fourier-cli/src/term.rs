@@ -0,0 +1,108 @@
+//! Live preview of the animation in the terminal
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use fourier_svg::{DrawData, TerminalMode, TerminalVisualizer, Visualizer};
+
+/// Raw mode and the alternate screen, left when dropped so the terminal is
+/// restored however the preview ends
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn enter() -> io::Result<TerminalGuard> {
+        if !io::stdout().is_terminal() {
+            return Err(io::Error::other("stdout is not a terminal"));
+        }
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Ctrl-C, q or Esc. Raw mode turns Ctrl-C into a key instead of a signal.
+pub fn is_quit(key: &KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && match key.code {
+            KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+            KeyCode::Char('q') | KeyCode::Esc => true,
+            _ => false,
+        }
+}
+
+/// Parse the --term-mode argument
+pub fn parse_mode(mode: &str) -> Result<TerminalMode, String> {
+    match mode {
+        "braille" => Ok(TerminalMode::Braille),
+        "half-block" => Ok(TerminalMode::HalfBlock),
+        _ => Err(format!(
+            "unknown terminal mode \"{}\", expected braille or half-block",
+            mode
+        )),
+    }
+}
+
+/// Play the animation full screen, sized to the terminal, until a quit key
+pub fn preview(visualizer: TerminalVisualizer, data: &[DrawData]) -> Result<(), String> {
+    visualizer.check(data).map_err(|e| e.to_string())?;
+    let _guard = TerminalGuard::enter().map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    let mut visualizer = visualizer;
+    loop {
+        // The last line is kept for the key help
+        let (columns, rows) = terminal::size().map_err(|e| e.to_string())?;
+        visualizer = visualizer.with_size(columns.max(1) as u32, rows.max(2) as u32 - 1);
+        if play(&visualizer, data, start).map_err(|e| e.to_string())? {
+            return Ok(());
+        }
+    }
+}
+
+/// Show the frame due at every tick until the terminal is resized (`false`)
+/// or a quit key is pressed (`true`)
+fn play(visualizer: &TerminalVisualizer, data: &[DrawData], start: Instant) -> io::Result<bool> {
+    let frames = visualizer.frames(data);
+    let (numerator, denominator) = visualizer.animation().frame_rate();
+    let frame_time = Duration::from_secs_f64(denominator as f64 / numerator as f64);
+    let (_, rows) = terminal::size()?;
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        let elapsed = start.elapsed().as_secs_f64();
+        let tick = (elapsed * numerator as f64 / denominator as f64) as usize;
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            Print(frames.frame(tick % frames.frame_count())),
+            cursor::MoveTo(0, rows.saturating_sub(1)),
+            SetAttribute(Attribute::Dim),
+            Print("q, Esc or Ctrl-C to quit"),
+            SetAttribute(Attribute::Reset),
+        )?;
+        stdout.flush()?;
+
+        // Wait for the next tick, handling the events meanwhile
+        let next = start + frame_time.mul_f64((tick + 1) as f64);
+        while let Some(timeout) = next.checked_duration_since(Instant::now()) {
+            if !event::poll(timeout)? {
+                break;
+            }
+            match event::read()? {
+                Event::Key(key) if is_quit(&key) => return Ok(true),
+                Event::Resize(..) => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
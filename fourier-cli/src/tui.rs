@@ -0,0 +1,413 @@
+//! Full-screen terminal UI to explore the coefficients of a shape
+//!
+//! The animation is redrawn with the terms chosen with the keys, next to the
+//! spectrum of radii, and the current state can be exported with any file
+//! backend.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use fourier_svg::{truncate, Bounds, DrawData, Epicycles, Theme, Truncation, Window};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::canvas::{Canvas, Circle, Context, Line as CanvasLine};
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, Paragraph};
+use ratatui::{Frame, Terminal};
+
+use crate::term::{is_quit, TerminalGuard};
+
+const TRUNCATIONS: [Truncation; 2] = [Truncation::LowestFrequencies, Truncation::LargestRadii];
+
+const WINDOWS: [Window; 4] = [
+    Window::Rectangular,
+    Window::Fejer,
+    Window::Lanczos,
+    Window::Hann,
+];
+
+/// Time between two redraws
+const TICK: Duration = Duration::from_millis(33);
+
+/// Length of the trail behind the pen, as a fraction of a period
+const TRAIL: f32 = 0.8;
+
+/// Points of the trail
+const TRAIL_SAMPLES: usize = 256;
+
+/// Bar value of the largest radius in the spectrum
+const SPECTRUM_SCALE: f32 = 1000.0;
+
+struct App<'a> {
+    data: Vec<DrawData>,
+    /// Rotating terms of the data
+    arms: usize,
+    terms: usize,
+    truncation: Truncation,
+    window: Window,
+    /// Periods drawn per second
+    speed: f32,
+    paused: bool,
+    time: f32,
+    /// Terms drawn, and their epicycles
+    drawn: Vec<DrawData>,
+    epicycles: Epicycles,
+    /// Area of the full drawing, kept whatever the terms
+    bounds: Bounds,
+    theme: Theme,
+    backends: &'a [&'a str],
+    /// Backend being chosen for an export
+    exporting: Option<usize>,
+    message: String,
+}
+
+impl<'a> App<'a> {
+    fn new(data: Vec<DrawData>, theme: Theme, backends: &'a [&'a str]) -> App<'a> {
+        let epicycles = Epicycles::new(&data);
+        let arms = epicycles.arms().len();
+        let mut app = App {
+            bounds: epicycles.bounds(TRAIL_SAMPLES),
+            arms,
+            terms: arms,
+            truncation: Truncation::LowestFrequencies,
+            window: Window::Rectangular,
+            speed: 0.12,
+            paused: false,
+            time: 0.0,
+            drawn: Vec::new(),
+            epicycles,
+            data,
+            theme,
+            backends,
+            exporting: None,
+            message: String::new(),
+        };
+        app.update();
+        app
+    }
+
+    /// Recompute the drawn terms after a change of settings
+    fn update(&mut self) {
+        self.terms = self.terms.clamp(1.min(self.arms), self.arms);
+        self.drawn = truncate(&self.data, self.terms, self.truncation, self.window);
+        self.epicycles = Epicycles::new(&self.drawn);
+    }
+
+    /// Change the settings with a key
+    fn key(&mut self, code: KeyCode) {
+        if let Some(backend) = self.exporting {
+            let count = self.backends.len();
+            match code {
+                KeyCode::Left => self.exporting = Some((backend + count - 1) % count),
+                KeyCode::Right => self.exporting = Some((backend + 1) % count),
+                KeyCode::Enter => self.exporting = None,
+                KeyCode::Esc => {
+                    self.exporting = None;
+                    self.message = "Export cancelled".to_string();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match code {
+            KeyCode::Up | KeyCode::Char('+') => self.terms += 1,
+            KeyCode::Down | KeyCode::Char('-') => self.terms = self.terms.saturating_sub(1),
+            KeyCode::PageUp => self.terms += 10,
+            KeyCode::PageDown => self.terms = self.terms.saturating_sub(10),
+            KeyCode::Home => self.terms = 1,
+            KeyCode::End => self.terms = self.arms,
+            KeyCode::Char('t') => self.truncation = next(&TRUNCATIONS, self.truncation),
+            KeyCode::Char('w') => self.window = next(&WINDOWS, self.window),
+            KeyCode::Right => self.speed *= 1.25,
+            KeyCode::Left => self.speed /= 1.25,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('e') => self.exporting = Some(0),
+            _ => return,
+        }
+        self.message.clear();
+        self.update();
+    }
+
+    /// Whether `key` ends the UI. Esc leaves the export choice first.
+    fn quits(&self, key: &KeyEvent) -> bool {
+        is_quit(key) && !(self.exporting.is_some() && key.code == KeyCode::Esc)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [animation, spectrum, status] = Layout::vertical([
+            Constraint::Min(6),
+            Constraint::Length(8),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        self.draw_animation(frame, animation);
+        self.draw_spectrum(frame, spectrum);
+
+        let settings = format!(
+            " {}/{} terms | {} | {} window | {:.3} periods/s{}",
+            self.terms,
+            self.arms,
+            self.truncation,
+            self.window,
+            self.speed,
+            if self.paused { " | paused" } else { "" }
+        );
+        let help = match self.exporting {
+            Some(backend) => format!(
+                " Export as < {} >   Left/Right choose, Enter export, Esc cancel",
+                self.backends[backend]
+            ),
+            None if !self.message.is_empty() => format!(" {}", self.message),
+            None => " Up/Down terms, PgUp/PgDn by 10, t truncation, w window, Left/Right speed, \
+                      Space pause, e export, q quit"
+                .to_string(),
+        };
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(settings), Line::styled(help, dim)]),
+            status,
+        );
+    }
+
+    fn draw_animation(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Epicycles ");
+        let inner = block.inner(area);
+
+        // Fit the drawing keeping its proportions, a character being about
+        // twice as high as wide
+        let (width, height) = (inner.width.max(1) as f64, inner.height.max(1) as f64 * 2.0);
+        let center = self.bounds.center();
+        let scale = (self.bounds.width() as f64 / width)
+            .max(self.bounds.height() as f64 / height)
+            .max(f64::EPSILON)
+            * 1.1;
+        let x_bounds = [
+            center.0 as f64 - scale * width / 2.0,
+            center.0 as f64 + scale * width / 2.0,
+        ];
+        // The canvas goes up, SVG coordinates go down
+        let y_bounds = [
+            -center.1 as f64 - scale * height / 2.0,
+            -center.1 as f64 + scale * height / 2.0,
+        ];
+
+        let mut canvas = Canvas::default()
+            .block(block)
+            .marker(Marker::Braille)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .paint(|ctx| self.paint(ctx));
+        if !self.theme.background.is_transparent() {
+            canvas = canvas.background_color(color(self.theme.background));
+        }
+        frame.render_widget(canvas, area);
+    }
+
+    fn paint(&self, ctx: &mut Context) {
+        let theme = &self.theme;
+        let background = theme.background;
+        // Translucent colors are blended with the background beforehand
+        let blend = |c: fourier_svg::Color| {
+            if background.is_transparent() {
+                color(c)
+            } else {
+                color(background.lerp(&c, c.a as f32 / 255.0))
+            }
+        };
+        let point = |(x, y): (f32, f32)| (x as f64, -y as f64);
+
+        let trail: Vec<(f64, f64)> = (0..=TRAIL_SAMPLES)
+            .map(|i| {
+                let t = self.time - TRAIL * (1.0 - i as f32 / TRAIL_SAMPLES as f32);
+                point(self.epicycles.tip(t))
+            })
+            .collect();
+        for (i, pair) in trail.windows(2).enumerate() {
+            let position = i as f32 / TRAIL_SAMPLES as f32;
+            ctx.draw(&CanvasLine {
+                x1: pair[0].0,
+                y1: pair[0].1,
+                x2: pair[1].0,
+                y2: pair[1].1,
+                color: blend(theme.trail_color(position)),
+            });
+        }
+        ctx.layer();
+
+        let joints = self.epicycles.joints(self.time);
+        for (arm, pair) in self.epicycles.arms().iter().zip(joints.windows(2)) {
+            let (x1, y1) = point(pair[0]);
+            let (x2, y2) = point(pair[1]);
+            ctx.draw(&Circle {
+                x: x1,
+                y: y1,
+                radius: arm.radius.abs() as f64,
+                color: blend(theme.circle_color(arm.frequency)),
+            });
+            ctx.draw(&CanvasLine {
+                x1,
+                y1,
+                x2,
+                y2,
+                color: blend(theme.arm_color(arm.frequency)),
+            });
+        }
+    }
+
+    /// Radii by frequency around 0, the drawn terms in color
+    fn draw_spectrum(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Spectrum ");
+        let half = (block.inner(area).width.saturating_sub(1) / 2) as f32;
+
+        let mut terms: Vec<&DrawData> = self
+            .data
+            .iter()
+            .filter(|d| d.frequency != 0.0 && d.frequency.abs() <= half)
+            .collect();
+        terms.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+        let largest = terms
+            .iter()
+            .map(|d| d.radius.abs())
+            .fold(f32::EPSILON, f32::max);
+
+        let drawn = |d: &DrawData| {
+            self.drawn
+                .iter()
+                .any(|kept| kept.frequency == d.frequency && kept.radius != 0.0)
+        };
+        let bars: Vec<Bar> = terms
+            .iter()
+            .map(|d| {
+                let style = if drawn(d) {
+                    Style::default().fg(color(self.theme.arm_color(d.frequency)))
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                Bar::default()
+                    .value((d.radius.abs() / largest * SPECTRUM_SCALE) as u64)
+                    .text_value(String::new())
+                    .style(style)
+            })
+            .collect();
+
+        let chart = BarChart::default()
+            .block(block)
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(1)
+            .bar_gap(0)
+            .max(SPECTRUM_SCALE as u64);
+        frame.render_widget(chart, area);
+    }
+}
+
+/// The entry after `current`, wrapping around
+fn next<T: Copy + PartialEq>(values: &[T], current: T) -> T {
+    let index = values.iter().position(|v| *v == current).unwrap_or(0);
+    values[(index + 1) % values.len()]
+}
+
+fn color(c: fourier_svg::Color) -> Color {
+    Color::Rgb(c.r, c.g, c.b)
+}
+
+/// Run the UI until a quit key. `export` renders the drawn terms with the
+/// backend of the given name, describing what was written.
+pub fn run(
+    data: Vec<DrawData>,
+    theme: Theme,
+    backends: &[&str],
+    export: impl Fn(&str, Vec<DrawData>) -> Result<String, String>,
+) -> Result<(), String> {
+    let _guard = TerminalGuard::enter().map_err(|e| e.to_string())?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(io::stdout())).map_err(|e| e.to_string())?;
+    let mut app = App::new(data, theme, backends);
+
+    let mut last = Instant::now();
+    loop {
+        terminal
+            .draw(|frame| app.draw(frame))
+            .map_err(|e| e.to_string())?;
+
+        if event::poll(TICK).map_err(|e| e.to_string())? {
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let export_to = app.exporting.filter(|_| key.code == KeyCode::Enter);
+                if app.quits(&key) {
+                    return Ok(());
+                }
+                app.key(key.code);
+
+                if let Some(backend) = export_to {
+                    let backend = app.backends[backend];
+                    app.message = format!("Exporting as {}...", backend);
+                    terminal
+                        .draw(|frame| app.draw(frame))
+                        .map_err(|e| e.to_string())?;
+                    app.message = export(backend, app.drawn.clone()).unwrap_or_else(|e| e);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if !app.paused {
+            app.time = (app.time + (now - last).as_secs_f32() * app.speed).fract();
+        }
+        last = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(backends: &'static [&'static str]) -> App<'static> {
+        let data = vec![
+            DrawData::new(0.0, 1.0, 0.0),
+            DrawData::new(1.0, 10.0, 0.0),
+            DrawData::new(-1.0, 5.0, 0.0),
+            DrawData::new(2.0, 2.0, 0.0),
+        ];
+        App::new(data, Theme::default(), backends)
+    }
+
+    #[test]
+    fn test_keys_clamp_terms_and_choose_exports() {
+        let mut app = app(&["svg", "gif", "code"]);
+        assert_eq!((app.arms, app.terms), (3, 3));
+        app.key(KeyCode::Up);
+        assert_eq!(app.terms, 3);
+        app.key(KeyCode::PageDown);
+        assert_eq!((app.terms, app.drawn.len()), (1, 2));
+        app.key(KeyCode::End);
+        assert_eq!(app.terms, 3);
+
+        app.key(KeyCode::Char('e'));
+        app.key(KeyCode::Left);
+        assert_eq!(app.exporting, Some(2));
+        app.key(KeyCode::Right);
+        assert_eq!(app.exporting, Some(0));
+        // Up changes nothing while choosing
+        app.key(KeyCode::Up);
+        assert_eq!((app.exporting, app.terms), (Some(0), 3));
+
+        let esc = KeyEvent::from(KeyCode::Esc);
+        assert!(!app.quits(&esc));
+        app.key(KeyCode::Esc);
+        assert_eq!(app.exporting, None);
+        assert_eq!(app.message, "Export cancelled");
+        assert!(app.quits(&esc));
+
+        // The next settings key clears the message
+        app.key(KeyCode::Char('w'));
+        assert_eq!(app.window, Window::Fejer);
+        assert!(app.message.is_empty());
+    }
+}
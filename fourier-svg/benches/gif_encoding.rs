@@ -0,0 +1,47 @@
+//! Compare the fixed-palette GIF encoding with per-frame NeuQuant quantisation
+//!
+//! Run with `cargo bench -p fourier-svg --bench gif_encoding`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fourier_svg::{process_svg_path, FourierConfig, GIFVisualizer, GifQuantization, Visualizer};
+
+const KIWI: &str = "M 60 200 C 20 120 80 40 180 40 C 260 40 300 110 340 130 \
+                    C 380 150 420 190 460 260 L 450 270 C 400 220 350 180 300 190 \
+                    C 250 200 200 230 160 250 L 170 300 L 150 300 L 130 250 \
+                    C 100 240 80 230 60 200 Z";
+
+fn bench_gif_encoding(c: &mut Criterion) {
+    let data = process_svg_path(KIWI, &FourierConfig::new(2048, 101));
+    let visualizer = |quantization| {
+        GIFVisualizer::default()
+            .with_dimensions(400, 300)
+            .with_frames(20)
+            .with_quantization(quantization)
+    };
+
+    let mut group = c.benchmark_group("gif_encoding");
+    group.sample_size(10);
+    for (name, quantization, frame_diff) in [
+        ("neuquant", GifQuantization::NeuQuant { speed: 1 }, false),
+        (
+            "neuquant_fast",
+            GifQuantization::NeuQuant { speed: 10 },
+            false,
+        ),
+        ("fixed_palette", GifQuantization::FixedPalette, false),
+        ("fixed_palette_diff", GifQuantization::FixedPalette, true),
+    ] {
+        let visualizer = visualizer(quantization).with_frame_diff(frame_diff);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                visualizer
+                    .render_to(data.clone(), &mut std::io::sink())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_gif_encoding);
+criterion_main!(benches);
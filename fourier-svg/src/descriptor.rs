@@ -0,0 +1,103 @@
+//! Fourier descriptors - Compare shapes through their coefficients
+//!
+//! The magnitudes of the Fourier coefficients form a classic shape
+//! descriptor. Dropping the DC term removes translation, dividing by the
+//! energy of the remaining terms removes scale, and keeping magnitudes only
+//! removes both rotation and the choice of start point. Tracing direction
+//! is normalised as well, so a shape drawn clockwise matches the same shape
+//! drawn counter-clockwise.
+
+use crate::DrawData;
+
+/// Default number of frequency pairs kept in a descriptor
+pub const DEFAULT_DESCRIPTOR_ORDER: usize = 16;
+
+/// Translation-, scale-, rotation- and start-point-invariant shape descriptor
+#[derive(Debug, Clone, PartialEq)]
+pub struct FourierDescriptor {
+    /// Number of frequency pairs (`1, -1, 2, -2, ...`) in `values`
+    pub order: usize,
+    /// Normalised magnitudes, ordered as frequencies `1, -1, 2, -2, ...`
+    pub values: Vec<f32>,
+}
+
+impl FourierDescriptor {
+    /// Build a descriptor from the coefficients of a shape
+    pub fn from_draw_data(data: &[DrawData], order: usize) -> FourierDescriptor {
+        let magnitude = |frequency: i32| -> f32 {
+            // Coefficients sharing a frequency are summed
+            let (re, im) = data
+                .iter()
+                .filter(|d| d.frequency.round() as i32 == frequency)
+                .fold((0.0, 0.0), |(re, im), d| {
+                    (re + d.radius * d.angle.cos(), im + d.radius * d.angle.sin())
+                });
+            (re * re + im * im).sqrt()
+        };
+
+        // Make the dominant rotation positive, so that reversing the
+        // tracing direction (which swaps k and -k) gives the same descriptor
+        let direction = if magnitude(-1) > magnitude(1) { -1 } else { 1 };
+
+        let mut values = Vec::with_capacity(order * 2);
+        for k in 1..=order as i32 {
+            values.push(magnitude(k * direction));
+            values.push(magnitude(-k * direction));
+        }
+
+        let energy = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if energy > 0.0 {
+            for v in values.iter_mut() {
+                *v /= energy;
+            }
+        }
+
+        FourierDescriptor { order, values }
+    }
+
+    /// Euclidean distance to another descriptor, 0 for identical shapes.
+    /// Descriptors of different orders are compared on their common part.
+    pub fn distance(&self, other: &FourierDescriptor) -> f32 {
+        self.values
+            .iter()
+            .zip(other.values.iter())
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+/// Distance between two shapes using descriptors of the default order
+pub fn shape_distance(a: &[DrawData], b: &[DrawData]) -> f32 {
+    FourierDescriptor::from_draw_data(a, DEFAULT_DESCRIPTOR_ORDER).distance(
+        &FourierDescriptor::from_draw_data(b, DEFAULT_DESCRIPTOR_ORDER),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_svg_path, FourierConfig};
+
+    #[test]
+    fn test_descriptor_is_invariant() {
+        let config = FourierConfig::new(2048, 65);
+        let square = process_svg_path("M 0 0 L 10 0 L 10 10 L 0 10 Z", &config);
+        // Translated, scaled, rotated by 45 degrees, started elsewhere, reversed
+        let moved = process_svg_path("M 100 70.71 L 170.71 0 L 100 -70.71 L 29.29 0 Z", &config);
+
+        assert!(shape_distance(&square, &moved) < 0.02);
+    }
+
+    #[test]
+    fn test_descriptor_separates_shapes() {
+        let config = FourierConfig::new(2048, 65);
+        let square = process_svg_path("M 0 0 L 10 0 L 10 10 L 0 10 Z", &config);
+        let triangle = process_svg_path("M 0 0 L 10 0 L 5 8.66 Z", &config);
+        let rectangle = process_svg_path("M 0 0 L 30 0 L 30 10 L 0 10 Z", &config);
+
+        assert!(shape_distance(&square, &triangle) > 0.05);
+        assert!(shape_distance(&square, &rectangle) > 0.05);
+        assert_eq!(shape_distance(&square, &square), 0.0);
+    }
+}
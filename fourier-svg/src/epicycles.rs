@@ -0,0 +1,124 @@
+//! Epicycle evaluation - Shared by every visualizer
+//!
+//! The coefficients describe the curve `z(t) = sum r_k e^(i (a_k + 2 PI f_k t))`
+//! with `t` running over `[0, 1)` for one full period. The DC term (frequency 0)
+//! does not rotate: it is the anchor from which the rotating arms are chained.
+
+use std::f32::consts::PI;
+
+use crate::DrawData;
+
+/// Chain of rotating arms evaluated in period-normalised time
+#[derive(Debug, Clone)]
+pub struct Epicycles {
+    center: (f32, f32),
+    arms: Vec<DrawData>,
+}
+
+impl Epicycles {
+    /// Build the chain, keeping the order of the rotating terms
+    pub fn new(data: &[DrawData]) -> Epicycles {
+        let mut center = (0.0, 0.0);
+        let mut arms = Vec::with_capacity(data.len());
+
+        for d in data {
+            if d.frequency == 0.0 {
+                center.0 += d.radius * d.angle.cos();
+                center.1 += d.radius * d.angle.sin();
+            } else {
+                arms.push(d.clone());
+            }
+        }
+
+        Epicycles { center, arms }
+    }
+
+    /// Fixed anchor of the chain, the sum of the DC terms
+    pub fn center(&self) -> (f32, f32) {
+        self.center
+    }
+
+    /// Rotating terms, in chaining order
+    pub fn arms(&self) -> &[DrawData] {
+        &self.arms
+    }
+
+    /// The same chain keeping only its first `arms` rotating terms
+    pub fn truncated(&self, arms: usize) -> Epicycles {
+        Epicycles {
+            center: self.center,
+            arms: self.arms[..arms.min(self.arms.len())].to_vec(),
+        }
+    }
+
+    /// Anchor followed by the tip of every arm at time `t`
+    pub fn joints(&self, t: f32) -> Vec<(f32, f32)> {
+        let mut joints = Vec::with_capacity(self.arms.len() + 1);
+        let (mut x, mut y) = self.center;
+        joints.push((x, y));
+
+        for arm in &self.arms {
+            let angle = arm.angle + 2.0 * PI * arm.frequency * t;
+            x += arm.radius * angle.cos();
+            y += arm.radius * angle.sin();
+            joints.push((x, y));
+        }
+
+        joints
+    }
+
+    /// Point drawn at time `t`, the tip of the last arm
+    pub fn tip(&self, t: f32) -> (f32, f32) {
+        self.arms.iter().fold(self.center, |(x, y), arm| {
+            let angle = arm.angle + 2.0 * PI * arm.frequency * t;
+            (x + arm.radius * angle.cos(), y + arm.radius * angle.sin())
+        })
+    }
+
+    /// The full curve over one period, sampled at `samples` evenly spaced times
+    pub fn trace(&self, samples: usize) -> Vec<(f32, f32)> {
+        (0..samples)
+            .map(|i| self.tip(i as f32 / samples as f32))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_svg_path, FourierConfig};
+
+    #[test]
+    fn test_joints_are_chained_from_center() {
+        let data = vec![
+            DrawData::new(1.0, 2.0, 0.0),
+            DrawData::new(0.0, 10.0, 0.0),
+            DrawData::new(-1.0, 1.0, PI / 2.0),
+        ];
+        let epicycles = Epicycles::new(&data);
+
+        assert_eq!(epicycles.center(), (10.0, 0.0));
+        let joints = epicycles.joints(0.0);
+        assert_eq!(joints.len(), 3);
+        assert_eq!(joints[1], (12.0, 0.0));
+        assert!((joints[2].0 - 12.0).abs() < 1e-5 && (joints[2].1 - 1.0).abs() < 1e-5);
+        assert_eq!(*joints.last().unwrap(), epicycles.tip(0.0));
+    }
+
+    #[test]
+    fn test_one_period_reconstructs_the_path() {
+        let config = FourierConfig::new(1024, 201);
+        let data = process_svg_path("M 0 0 L 40 0 L 40 40 L 0 40 Z", &config);
+        let epicycles = Epicycles::new(&data);
+
+        // Starts at the first point and reaches the opposite corner halfway
+        let start = epicycles.tip(0.0);
+        let halfway = epicycles.tip(0.5);
+        assert!(start.0.abs() < 1.0 && start.1.abs() < 1.0);
+        assert!((halfway.0 - 40.0).abs() < 1.0 && (halfway.1 - 40.0).abs() < 1.0);
+
+        // Periodic
+        let end = epicycles.tip(1.0);
+        assert!((end.0 - start.0).abs() < 1e-2 && (end.1 - start.1).abs() < 1e-2);
+    }
+}
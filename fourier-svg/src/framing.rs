@@ -0,0 +1,207 @@
+//! Framing - Place the drawing inside the output frame
+//!
+//! The bounding box covers the reconstructed curve and every epicycle joint
+//! over a full period, so neither the drawing nor the arms leave the frame.
+
+use crate::epicycles::Epicycles;
+use crate::morph::Morph;
+
+/// Number of instants sampled when measuring the extent of the epicycles
+pub const BOUNDS_SAMPLES: usize = 512;
+
+/// Axis-aligned bounding box in drawing coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Bounds {
+    /// Box containing no point, grows with `include`
+    pub fn empty() -> Bounds {
+        Bounds {
+            min_x: f32::INFINITY,
+            min_y: f32::INFINITY,
+            max_x: f32::NEG_INFINITY,
+            max_y: f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_y > self.max_y
+    }
+
+    pub fn include(&mut self, (x, y): (f32, f32)) {
+        if !x.is_finite() || !y.is_finite() {
+            return;
+        }
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    pub fn union(&mut self, other: &Bounds) {
+        if !other.is_empty() {
+            self.include((other.min_x, other.min_y));
+            self.include((other.max_x, other.max_y));
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (
+            (self.min_x + self.max_x) / 2.0,
+            (self.min_y + self.max_y) / 2.0,
+        )
+    }
+}
+
+impl Epicycles {
+    /// Extent of the curve and of every arm over one period
+    pub fn bounds(&self, samples: usize) -> Bounds {
+        let mut bounds = Bounds::empty();
+        for i in 0..samples.max(1) {
+            for joint in self.joints(i as f32 / samples.max(1) as f32) {
+                bounds.include(joint);
+            }
+        }
+        bounds
+    }
+}
+
+/// Extent of the epicycles over every stage of a morph
+pub fn morph_bounds(morph: &Morph, samples: usize) -> Bounds {
+    let mut bounds = Bounds::empty();
+    for stage in 0..=4 {
+        let epicycles = Epicycles::new(&morph.at(stage as f32 / 4.0));
+        bounds.union(&epicycles.bounds(samples));
+    }
+    bounds
+}
+
+/// Mapping from drawing coordinates to pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    /// Pixels per drawing unit
+    pub scale: f32,
+    /// Pixel position of the drawing origin
+    pub offset: (f32, f32),
+}
+
+impl Transform {
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            x * self.scale + self.offset.0,
+            y * self.scale + self.offset.1,
+        )
+    }
+}
+
+/// How the drawing is placed in the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Framing {
+    /// Margin kept free around the drawing, in pixels
+    pub padding: f32,
+    /// Fixed scale in pixels per drawing unit, `None` to fit the frame
+    pub scale: Option<f32>,
+    /// Pixel position of the drawing origin, `None` to center the drawing
+    pub origin: Option<(f32, f32)>,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            padding: 20.0,
+            scale: None,
+            origin: None,
+        }
+    }
+}
+
+impl Framing {
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn with_origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = Some((x, y));
+        self
+    }
+
+    /// Transform placing `bounds` in a `width` x `height` frame
+    pub fn transform(&self, bounds: &Bounds, width: f32, height: f32) -> Transform {
+        let scale = self.scale.unwrap_or_else(|| {
+            if bounds.is_empty() {
+                return 1.0;
+            }
+            let available_x = (width - 2.0 * self.padding).max(1.0);
+            let available_y = (height - 2.0 * self.padding).max(1.0);
+            let scale_x = available_x / bounds.width();
+            let scale_y = available_y / bounds.height();
+            let scale = scale_x.min(scale_y);
+            if scale.is_finite() && scale > 0.0 {
+                scale
+            } else {
+                1.0
+            }
+        });
+
+        let offset = self.origin.unwrap_or_else(|| {
+            if bounds.is_empty() {
+                return (width / 2.0, height / 2.0);
+            }
+            let (cx, cy) = bounds.center();
+            (width / 2.0 - cx * scale, height / 2.0 - cy * scale)
+        });
+
+        Transform { scale, offset }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DrawData;
+
+    #[test]
+    fn test_fit_keeps_arms_in_frame() {
+        // A circle of radius 100 around (1000, 1000)
+        let data = vec![
+            DrawData::new(0.0, 1000.0 * 2f32.sqrt(), std::f32::consts::FRAC_PI_4),
+            DrawData::new(1.0, 100.0, 0.0),
+        ];
+        let epicycles = Epicycles::new(&data);
+        let transform = Framing::default().transform(&epicycles.bounds(64), 800.0, 600.0);
+
+        assert!((transform.scale - 2.8).abs() < 1e-3);
+        let (x, y) = transform.apply((1000.0, 1000.0));
+        assert!((x - 400.0).abs() < 1e-2 && (y - 300.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_fixed_scale_and_origin() {
+        let mut bounds = Bounds::empty();
+        bounds.include((-5.0, -5.0));
+        bounds.include((5.0, 5.0));
+
+        let framing = Framing::default().with_scale(3.0).with_origin(10.0, 20.0);
+        let transform = framing.transform(&bounds, 800.0, 600.0);
+        assert_eq!(transform.apply((1.0, 1.0)), (13.0, 23.0));
+    }
+}
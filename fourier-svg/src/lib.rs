@@ -3,28 +3,67 @@
 //! This library provides core functionality for:
 //! - Parsing SVG paths
 //! - Computing Fourier transforms
-//! - Rendering visualizations (HTML, GIF, export)
+//! - Rendering visualizations (HTML, SVG, animated SVG, GIF, APNG, WebP, PNG frames, Y4M, terminal,
+//!   source code, LaTeX/Desmos equations, G-code/HPGL, CSV/NPY arrays, export)
+//! - Anti-aliased rasterisation of epicycles
+//! - Themes shared by every visualizer
 //! - Loading/saving Fourier data
+//! - Morphing one shape into another
+//! - Comparing shapes with Fourier descriptors
+//! - Detecting and enforcing symmetries
+//! - Truncating and windowing the series
 
+pub mod descriptor;
+pub mod epicycles;
 pub mod fft_drawer;
+pub mod framing;
+pub mod morph;
 pub mod path_util;
 pub mod processor;
+pub mod raster;
+pub mod symmetry;
+pub mod theme;
+pub mod truncation;
 pub mod visualizer;
 
 // Re-export commonly used types
+pub use descriptor::{shape_distance, FourierDescriptor, DEFAULT_DESCRIPTOR_ORDER};
+pub use epicycles::Epicycles;
 pub use fft_drawer::DrawData;
+pub use framing::{Bounds, Framing, Transform};
+pub use morph::{interpolate, Morph, MorphOptions, MorphTarget};
 pub use path_util::{build_path_from_svg, path_to_fft};
 pub use processor::{
     build_draw_data_from_fft, combine_layers, extract_all_paths_from_file,
-    extract_first_path_from_file, process_multiple_paths, process_source, process_svg_path,
-    FourierConfig, FourierSource, PathLayer,
+    extract_first_path_from_file, extract_view_box_from_file, process_multiple_paths,
+    process_source, process_svg_path, sample_svg_path, FourierConfig, FourierSource, PathLayer,
 };
+pub use raster::{render_epicycles, Canvas, RasterStyle};
+pub use symmetry::{
+    detect_symmetries, enforce_symmetry, mirror_symmetry, rotational_symmetry_confidence,
+    DetectedSymmetry, Symmetry, DEFAULT_MAX_SYMMETRY_ORDER, DEFAULT_SYMMETRY_THRESHOLD,
+};
+pub use theme::{Color, Theme, THEME_PRESETS};
+pub use truncation::{truncate, Truncation, Window};
 pub use visualizer::{
+    animated_svg_visualizer::AnimatedSVGVisualizer,
+    animation::Animation,
+    apng_visualizer::APNGVisualizer,
+    array_visualizer::{ArrayContent, ArrayFormat, ArrayVisualizer},
+    code_visualizer::{CodeLanguage, CodeVisualizer},
     export_visualizer::ExportVisualizer,
     export_visualizer::{
-        export_to_draw_data, load_fourier_export, ExportMetadata, FourierCoefficient, FourierExport,
+        export_to_draw_data, load_fourier_export, ExportLayer, ExportMetadata, FourierCoefficient,
+        FourierExport,
     },
-    gif_visualizer::GIFVisualizer,
-    html_visualizer::HTMLVisualizer,
-    Visualizer,
+    gif_visualizer::{GIFVisualizer, GifQuantization},
+    html_visualizer::{HTMLVisualizer, HtmlOptions},
+    math_visualizer::{MathFormat, MathVisualizer},
+    plotter_visualizer::{PlotterFormat, PlotterVisualizer},
+    png_sequence_visualizer::PNGSequenceVisualizer,
+    svg_visualizer::SVGVisualizer,
+    terminal_visualizer::{TerminalFrames, TerminalMode, TerminalVisualizer},
+    webp_visualizer::WebPVisualizer,
+    y4m_visualizer::Y4MVisualizer,
+    RenderSummary, Visualizer, VisualizerError,
 };
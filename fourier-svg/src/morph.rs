@@ -0,0 +1,293 @@
+//! Shape morphing - Blend one set of Fourier coefficients into another
+//!
+//! Both shapes are aligned by frequency so that every epicycle of the
+//! source shape has a partner in the target shape. Radius is interpolated
+//! linearly and phase along the shortest arc, which keeps the arms from
+//! spinning a full turn mid-morph.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use rustfft::num_complex::Complex;
+
+use crate::DrawData;
+
+/// Number of candidate start points tried when aligning start points
+const START_POINT_STEPS: usize = 2048;
+
+/// Options for building a morph
+#[derive(Debug, Clone, Default)]
+pub struct MorphOptions {
+    /// Shift the start point of the target shape so that it traces in step
+    /// with the source shape, minimising the distance travelled while morphing
+    pub align_start_point: bool,
+}
+
+impl MorphOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_start_point_alignment(mut self, align: bool) -> Self {
+        self.align_start_point = align;
+        self
+    }
+}
+
+/// A coefficient of the source shape matched with the target coefficient
+/// of the same frequency
+#[derive(Debug, Clone)]
+pub struct MorphPair {
+    pub frequency: f32,
+    pub from_radius: f32,
+    pub from_angle: f32,
+    pub to_radius: f32,
+    /// Target angle, unwrapped so that `to_angle - from_angle` lies in `[-PI, PI]`
+    pub to_angle: f32,
+}
+
+impl MorphPair {
+    /// Interpolate this pair, `t` going from 0 (source) to 1 (target)
+    pub fn at(&self, t: f32) -> DrawData {
+        DrawData::new(
+            self.frequency,
+            self.from_radius + (self.to_radius - self.from_radius) * t,
+            self.from_angle + (self.to_angle - self.from_angle) * t,
+        )
+    }
+}
+
+/// Two coefficient sets aligned by frequency, ready to be interpolated
+#[derive(Debug, Clone)]
+pub struct Morph {
+    pub pairs: Vec<MorphPair>,
+}
+
+impl Morph {
+    pub fn new(from: &[DrawData], to: &[DrawData], options: &MorphOptions) -> Morph {
+        let mut aligned = align_by_frequency(from, to);
+
+        if options.align_start_point {
+            let shift = best_start_point_shift(&aligned);
+            for (frequency, _, target) in aligned.iter_mut() {
+                *target *= Complex::from_polar(1.0, 2.0 * PI * *frequency * shift);
+            }
+        }
+
+        let pairs = aligned
+            .into_iter()
+            .map(|(frequency, source, target)| {
+                let (from_radius, mut from_angle) = source.to_polar();
+                let (to_radius, mut to_angle) = target.to_polar();
+
+                // A vanished coefficient has no meaningful phase, borrow the
+                // other one so the arm only grows or shrinks
+                if from_radius == 0.0 {
+                    from_angle = to_angle;
+                } else if to_radius == 0.0 {
+                    to_angle = from_angle;
+                }
+
+                MorphPair {
+                    frequency,
+                    from_radius,
+                    from_angle,
+                    to_radius,
+                    to_angle: from_angle + wrap_angle(to_angle - from_angle),
+                }
+            })
+            .collect();
+
+        Morph { pairs }
+    }
+
+    /// Coefficients of the in-between shape, `t` going from 0 (source) to 1 (target)
+    pub fn at(&self, t: f32) -> Vec<DrawData> {
+        let t = t.clamp(0.0, 1.0);
+        self.pairs.iter().map(|p| p.at(t)).collect()
+    }
+
+    /// The source shape, aligned to the target
+    pub fn from_data(&self) -> Vec<DrawData> {
+        self.at(0.0)
+    }
+
+    /// The target shape, aligned to the source
+    pub fn to_data(&self) -> Vec<DrawData> {
+        self.at(1.0)
+    }
+}
+
+/// A shape to morph into while an animation plays
+#[derive(Debug, Clone)]
+pub struct MorphTarget {
+    /// Coefficients of the target shape
+    pub data: Vec<DrawData>,
+    /// Length of the morph in seconds
+    pub duration: f32,
+    pub options: MorphOptions,
+}
+
+impl MorphTarget {
+    pub fn new(data: Vec<DrawData>, duration: f32) -> Self {
+        Self {
+            data,
+            duration,
+            options: MorphOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: MorphOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Eased morph progress after `elapsed` seconds
+    pub fn progress(&self, elapsed: f32) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        ease_in_out(elapsed / self.duration)
+    }
+}
+
+/// Interpolate between two coefficient sets with default options
+pub fn interpolate(from: &[DrawData], to: &[DrawData], t: f32) -> Vec<DrawData> {
+    Morph::new(from, to, &MorphOptions::default()).at(t)
+}
+
+/// Match the coefficients of two shapes by frequency
+///
+/// Coefficients sharing a frequency (e.g. from combined layers) are summed.
+/// A frequency present in only one shape is paired with a zero coefficient.
+/// The order of `from` is kept, followed by the frequencies only in `to`.
+pub fn align_by_frequency(
+    from: &[DrawData],
+    to: &[DrawData],
+) -> Vec<(f32, Complex<f32>, Complex<f32>)> {
+    let mut aligned: Vec<(f32, Complex<f32>, Complex<f32>)> = Vec::new();
+    let mut index: HashMap<i64, usize> = HashMap::new();
+
+    for (is_target, data) in [(false, from), (true, to)] {
+        for d in data {
+            let key = frequency_key(d.frequency);
+            let slot = *index.entry(key).or_insert_with(|| {
+                aligned.push((d.frequency, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)));
+                aligned.len() - 1
+            });
+            let value = Complex::from_polar(d.radius, d.angle);
+            if is_target {
+                aligned[slot].2 += value;
+            } else {
+                aligned[slot].1 += value;
+            }
+        }
+    }
+
+    aligned
+}
+
+/// Find the parameter shift `s` that, applied to the target, best matches
+/// the source: maximises `Re(sum conj(a_k) * b_k * e^(2 PI i k s))`
+fn best_start_point_shift(aligned: &[(f32, Complex<f32>, Complex<f32>)]) -> f32 {
+    let score = |shift: f32| -> f32 {
+        aligned
+            .iter()
+            .filter(|(frequency, _, _)| *frequency != 0.0)
+            .map(|(frequency, a, b)| {
+                (a.conj() * b * Complex::from_polar(1.0, 2.0 * PI * frequency * shift)).re
+            })
+            .sum()
+    };
+
+    let mut best_shift = 0.0;
+    let mut best_score = f32::MIN;
+    for step in 0..START_POINT_STEPS {
+        let shift = step as f32 / START_POINT_STEPS as f32;
+        let s = score(shift);
+        if s > best_score {
+            best_score = s;
+            best_shift = shift;
+        }
+    }
+    best_shift
+}
+
+/// Wrap an angle into `[-PI, PI]`
+pub(crate) fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+fn frequency_key(frequency: f32) -> i64 {
+    (frequency * 1000.0).round() as i64
+}
+
+/// Standard smoothstep easing, used to start and end morphs gently
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_svg_path, FourierConfig};
+
+    #[test]
+    fn test_align_by_frequency_fills_missing() {
+        let from = vec![DrawData::new(0.0, 1.0, 0.0), DrawData::new(1.0, 2.0, 0.5)];
+        let to = vec![DrawData::new(1.0, 3.0, 0.5), DrawData::new(-1.0, 1.0, 0.0)];
+
+        let aligned = align_by_frequency(&from, &to);
+        assert_eq!(aligned.len(), 3);
+        assert_eq!(aligned[2].0, -1.0);
+        assert_eq!(aligned[2].1.norm(), 0.0);
+        assert_eq!(aligned[0].2.norm(), 0.0);
+    }
+
+    #[test]
+    fn test_phase_takes_shortest_path() {
+        let from = vec![DrawData::new(1.0, 1.0, 3.0)];
+        let to = vec![DrawData::new(1.0, 1.0, -3.0)];
+
+        let morph = Morph::new(&from, &to, &MorphOptions::default());
+        let halfway = &morph.at(0.5)[0];
+        // Going through PI rather than through 0
+        assert!((halfway.angle.cos() + 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_morph_endpoints_match_inputs() {
+        let config = FourierConfig::new(1024, 31);
+        let from = process_svg_path("M 0 0 L 10 10 L 20 0 Z", &config);
+        let to = process_svg_path("M 0 0 L 0 20 L 20 20 L 20 0 Z", &config);
+
+        let morph = Morph::new(&from, &to, &MorphOptions::default());
+        for (d, m) in to.iter().zip(morph.at(1.0).iter()) {
+            assert_eq!(d.frequency, m.frequency);
+            assert!((d.radius - m.radius).abs() < 1e-4);
+            assert!(wrap_angle(d.angle - m.angle).abs() < 1e-3 || d.radius < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_start_point_alignment_undoes_shift() {
+        let config = FourierConfig::new(1024, 31);
+        let from = process_svg_path("M 0 0 L 0 20 L 30 20 L 30 0 Z", &config);
+        // Same rectangle started from another corner
+        let to = process_svg_path("M 30 20 L 30 0 L 0 0 L 0 20 Z", &config);
+
+        let options = MorphOptions::new().with_start_point_alignment(true);
+        let morph = Morph::new(&from, &to, &options);
+        let distance: f32 = morph
+            .pairs
+            .iter()
+            .map(|p| {
+                (Complex::from_polar(p.from_radius, p.from_angle)
+                    - Complex::from_polar(p.to_radius, p.to_angle))
+                .norm()
+            })
+            .sum();
+        assert!(distance < 1.0, "distance after alignment: {}", distance);
+    }
+}
@@ -172,12 +172,18 @@ pub fn construct_sample_points(
     samples
 }
 
-pub fn path_to_fft(path: Path, n_sample: usize) -> Vec<Complex<f32>> {
-    let path_length = compute_path_length(&path);
-    let mut samples = construct_sample_points(&path, path_length, n_sample);
+/// At most `n_sample` points evenly spaced along the path
+pub fn resample_path(path: &Path, n_sample: usize) -> Vec<Complex<f32>> {
+    let path_length = compute_path_length(path);
+    let mut samples = construct_sample_points(path, path_length, n_sample);
 
     // Truncate if too many samples
     samples.truncate(n_sample);
+    samples
+}
+
+pub fn path_to_fft(path: Path, n_sample: usize) -> Vec<Complex<f32>> {
+    let mut samples = resample_path(&path, n_sample);
 
     // Pad with zeros if not enough samples
     while samples.len() < n_sample {
@@ -3,7 +3,8 @@
 //! This module provides unified SVG/Fourier processing functionality
 //! that can be shared between CLI, Tauri, and GPUI applications.
 
-use crate::{build_path_from_svg, path_to_fft, DrawData};
+use crate::path_util::resample_path;
+use crate::{build_path_from_svg, path_to_fft, Bounds, DrawData};
 use rustfft::num_complex::Complex;
 
 /// Configuration for Fourier processing
@@ -51,6 +52,17 @@ pub fn process_svg_path(svg_path: &str, config: &FourierConfig) -> Vec<DrawData>
     build_draw_data_from_fft(&fft_result, config.num_wave)
 }
 
+/// Points of an SVG path as sampled before the transform, at most
+/// `num_sample` of them: the zeros the transform pads a short path with are
+/// left out
+pub fn sample_svg_path(svg_path: &str, config: &FourierConfig) -> Vec<(f32, f32)> {
+    let path = build_path_from_svg(svg_path);
+    resample_path(&path, config.num_sample)
+        .into_iter()
+        .map(|c| (c.re, c.im))
+        .collect()
+}
+
 /// Build DrawData from FFT result
 pub fn build_draw_data_from_fft(fft_result: &[Complex<f32>], num_wave: usize) -> Vec<DrawData> {
     let mut result = Vec::new();
@@ -96,6 +108,60 @@ pub fn extract_first_path_from_file(file_path: &str) -> Result<String, String> {
     }
 }
 
+/// Area of the drawing declared by the root element of an SVG file: its
+/// `viewBox`, or else its `width` and `height` in user units
+pub fn extract_view_box_from_file(file_path: &str) -> Result<Option<Bounds>, String> {
+    let mut content = String::new();
+
+    match svg::open(file_path, &mut content) {
+        Ok(doc) => {
+            for event in doc {
+                if let svg::parser::Event::Tag(svg::node::element::tag::SVG, _, attributes) = event
+                {
+                    let numbers = |name: &str| -> Option<Vec<f32>> {
+                        attributes.get(name).map(|v| {
+                            v.split(|c: char| c == ',' || c.is_whitespace())
+                                .filter(|n| !n.is_empty())
+                                .map(|n| n.trim_end_matches("px").parse().unwrap_or(f32::NAN))
+                                .collect()
+                        })
+                    };
+                    let (x, y, width, height) =
+                        match (numbers("viewBox"), numbers("width"), numbers("height")) {
+                            (Some(v), _, _) => {
+                                let valid = v.len() == 4
+                                    && v[2] > 0.0
+                                    && v[3] > 0.0
+                                    && v[0].is_finite()
+                                    && v[1].is_finite();
+                                if !valid {
+                                    return Err(format!("Invalid SVG viewBox in {}", file_path));
+                                }
+                                (v[0], v[1], v[2], v[3])
+                            }
+                            // Sizes in other units than px, or in percent, say
+                            // nothing of the user units: fit the drawing instead
+                            (None, Some(w), Some(h))
+                                if w.len() == 1 && h.len() == 1 && w[0] > 0.0 && h[0] > 0.0 =>
+                            {
+                                (0.0, 0.0, w[0], h[0])
+                            }
+                            _ => return Ok(None),
+                        };
+                    return Ok(Some(Bounds {
+                        min_x: x,
+                        min_y: y,
+                        max_x: x + width,
+                        max_y: y + height,
+                    }));
+                }
+            }
+            Ok(None)
+        }
+        Err(e) => Err(format!("Failed to open SVG file: {}", e)),
+    }
+}
+
 /// Extract all paths from an SVG file
 pub fn extract_all_paths_from_file(file_path: &str) -> Result<Vec<(String, String)>, String> {
     let mut content = String::new();
@@ -234,6 +300,50 @@ mod tests {
         assert!(!result.is_empty());
     }
 
+    #[test]
+    fn test_view_box_from_file() {
+        let dir = std::env::temp_dir();
+        let with_view_box = dir.join("fourier_svg_view_box.svg");
+        let with_size = dir.join("fourier_svg_size.svg");
+        std::fs::write(
+            &with_view_box,
+            r#"<svg viewBox="-50 0,750 600" width="10"><path d="M 0 0 L 1 1"/></svg>"#,
+        )
+        .unwrap();
+        std::fs::write(&with_size, r#"<svg width="800px" height="600px"></svg>"#).unwrap();
+
+        let view_box = extract_view_box_from_file(with_view_box.to_str().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!((view_box.min_x, view_box.max_x), (-50.0, 700.0));
+        assert_eq!((view_box.min_y, view_box.max_y), (0.0, 600.0));
+        let size = extract_view_box_from_file(with_size.to_str().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!((size.width(), size.height()), (800.0, 600.0));
+
+        std::fs::remove_file(with_view_box).unwrap();
+        std::fs::remove_file(with_size).unwrap();
+    }
+
+    #[test]
+    fn test_view_box_from_file_with_units() {
+        let dir = std::env::temp_dir();
+        let with_units = dir.join("fourier_svg_units.svg");
+        let malformed = dir.join("fourier_svg_malformed.svg");
+        std::fs::write(&with_units, r#"<svg width="100%" height="10mm"></svg>"#).unwrap();
+        std::fs::write(&malformed, r#"<svg viewBox="0 0 100"></svg>"#).unwrap();
+
+        assert_eq!(
+            extract_view_box_from_file(with_units.to_str().unwrap()).unwrap(),
+            None
+        );
+        assert!(extract_view_box_from_file(malformed.to_str().unwrap()).is_err());
+
+        std::fs::remove_file(with_units).unwrap();
+        std::fs::remove_file(malformed).unwrap();
+    }
+
     #[test]
     fn test_each_path_gets_independent_fft() {
         // Create two different paths
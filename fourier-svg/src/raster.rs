@@ -0,0 +1,448 @@
+//! Raster - Anti-aliased drawing of epicycles into RGBA images
+//!
+//! Every shape is rasterised by computing, for each pixel center near it, the
+//! distance to the ideal shape and turning it into a coverage between 0 and 1.
+//! Frames can also be drawn at a multiple of the output size and box-filtered
+//! down, which smooths the many short segments of a trail.
+
+use image::{Rgba, RgbaImage};
+
+use crate::epicycles::Epicycles;
+use crate::framing::Transform;
+use crate::theme::Theme;
+
+/// Appearance of a rasterised frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterStyle {
+    /// Colors and line widths, in output pixels
+    pub theme: Theme,
+    /// Draw the orbit of every arm
+    pub circles: bool,
+    /// Draw an arrowhead at the end of every arm
+    pub arrowheads: bool,
+    /// Fade the trail out from the pen to its oldest point
+    pub fade_trail: bool,
+    /// Frames are drawn at this multiple of the output size, then scaled down
+    pub supersampling: u32,
+}
+
+impl Default for RasterStyle {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            circles: true,
+            arrowheads: true,
+            fade_trail: true,
+            supersampling: 2,
+        }
+    }
+}
+
+impl RasterStyle {
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_circles(mut self, circles: bool) -> Self {
+        self.circles = circles;
+        self
+    }
+
+    pub fn with_arrowheads(mut self, arrowheads: bool) -> Self {
+        self.arrowheads = arrowheads;
+        self
+    }
+
+    pub fn with_fade_trail(mut self, fade_trail: bool) -> Self {
+        self.fade_trail = fade_trail;
+        self
+    }
+
+    pub fn with_supersampling(mut self, supersampling: u32) -> Self {
+        self.supersampling = supersampling.max(1);
+        self
+    }
+}
+
+/// RGBA image with anti-aliased drawing primitives, in pixel coordinates
+pub struct Canvas {
+    image: RgbaImage,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgba<u8>) -> Canvas {
+        Canvas {
+            image: RgbaImage::from_pixel(width, height, background),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    /// Composite `color` over a pixel, `coverage` scaling its alpha
+    pub fn blend(&mut self, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width() as i64 || y >= self.height() as i64 {
+            return;
+        }
+        let source_alpha = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+        if source_alpha <= 0.0 {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let dest_alpha = pixel[3] as f32 / 255.0;
+        let alpha = source_alpha + dest_alpha * (1.0 - source_alpha);
+        for c in 0..3 {
+            let value = (color[c] as f32 * source_alpha
+                + pixel[c] as f32 * dest_alpha * (1.0 - source_alpha))
+                / alpha;
+            pixel[c] = value.round() as u8;
+        }
+        pixel[3] = (alpha * 255.0).round() as u8;
+    }
+
+    /// Segment with round caps
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Rgba<u8>) {
+        let (w, h) = (self.width(), self.height());
+        line_coverage(w, h, from, to, width, |x, y, c| self.blend(x, y, color, c));
+    }
+
+    /// Connected segments, `color` giving the color at each position along
+    /// the line, from 0 at the first point to 1 at the last
+    ///
+    /// Each pixel keeps the strongest of the segments touching it, so
+    /// translucent joints are not darkened by overlapping caps.
+    pub fn polyline(&mut self, points: &[(f32, f32)], width: f32, color: impl Fn(f32) -> Rgba<u8>) {
+        let (w, h) = (self.width(), self.height());
+        let segments = points.len().saturating_sub(1);
+        let colors: Vec<Rgba<u8>> = (0..segments)
+            .map(|i| color((i + 1) as f32 / segments as f32))
+            .collect();
+
+        // Strongest coverage of every pixel and the segment providing it
+        let mut mask = vec![(0.0f32, 0usize); w as usize * h as usize];
+        for (i, pair) in points.windows(2).enumerate() {
+            let alpha = colors[i][3] as f32 / 255.0;
+            line_coverage(w, h, pair[0], pair[1], width, |x, y, c| {
+                let m = &mut mask[y as usize * w as usize + x as usize];
+                let strength = c.min(1.0) * alpha;
+                if strength > m.0 {
+                    *m = (strength, i);
+                }
+            });
+        }
+        for (i, (strength, segment)) in mask.into_iter().enumerate() {
+            if strength > 0.0 {
+                let (x, y) = (i % w as usize, i / w as usize);
+                let mut color = colors[segment];
+                color[3] = 255;
+                self.blend(x as i64, y as i64, color, strength);
+            }
+        }
+    }
+
+    /// Circle outline
+    pub fn circle(&mut self, center: (f32, f32), radius: f32, width: f32, color: Rgba<u8>) {
+        if ![center.0, center.1, radius, width]
+            .iter()
+            .all(|v| v.is_finite())
+        {
+            return;
+        }
+        let half_width = width / 2.0;
+        let outer = radius + half_width + 1.0;
+        let inner = radius - half_width - 1.0;
+
+        let first = (center.1 - outer).floor().max(0.0) as i64;
+        let last = (center.1 + outer).ceil().min(self.height() as f32 - 1.0) as i64;
+        for y in first..=last {
+            let dy = y as f32 + 0.5 - center.1;
+            if dy.abs() > outer {
+                continue;
+            }
+            let x_outer = (outer * outer - dy * dy).sqrt();
+            let x_inner = if inner > 0.0 && dy.abs() < inner {
+                (inner * inner - dy * dy).sqrt()
+            } else {
+                0.0
+            };
+
+            // Only the two spans crossing the ring, or one across the middle
+            let spans = if x_inner > 0.0 {
+                vec![
+                    (center.0 - x_outer, center.0 - x_inner),
+                    (center.0 + x_inner, center.0 + x_outer),
+                ]
+            } else {
+                vec![(center.0 - x_outer, center.0 + x_outer)]
+            };
+            for (from, to) in spans {
+                let from = from.floor().max(0.0) as i64;
+                let to = to.ceil().min(self.width() as f32 - 1.0) as i64;
+                for x in from..=to {
+                    let dx = x as f32 + 0.5 - center.0;
+                    let distance = ((dx * dx + dy * dy).sqrt() - radius).abs();
+                    let c = half_width + 0.5 - distance;
+                    if c > 0.0 {
+                        self.blend(x, y, color, c);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Filled triangle
+    pub fn triangle(&mut self, points: [(f32, f32); 3], color: Rgba<u8>) {
+        if !points.iter().all(|p| p.0.is_finite() && p.1.is_finite()) {
+            return;
+        }
+        let [a, b, c] = points;
+        let area = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        if area.abs() < 1e-6 {
+            return;
+        }
+        // Counter-clockwise edges, so signed distances are positive inside
+        let edges = if area > 0.0 {
+            [(a, b), (b, c), (c, a)]
+        } else {
+            [(a, c), (c, b), (b, a)]
+        };
+        let distance = |(p, q): ((f32, f32), (f32, f32)), x: f32, y: f32| {
+            let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+            (ex * (y - p.1) - ey * (x - p.0)) / (ex * ex + ey * ey).sqrt()
+        };
+
+        let min_x = a.0.min(b.0).min(c.0).floor().max(0.0) as i64;
+        let max_x = a.0.max(b.0).max(c.0).ceil().min(self.width() as f32 - 1.0) as i64;
+        let min_y = a.1.min(b.1).min(c.1).floor().max(0.0) as i64;
+        let max_y = a.1.max(b.1).max(c.1).ceil().min(self.height() as f32 - 1.0) as i64;
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let inside = edges
+                    .iter()
+                    .map(|edge| distance(*edge, px, py))
+                    .fold(f32::INFINITY, f32::min);
+                let c = inside + 0.5;
+                if c > 0.0 {
+                    self.blend(x, y, color, c);
+                }
+            }
+        }
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Average every `factor` x `factor` block into one pixel
+    pub fn downsample(self, factor: u32) -> RgbaImage {
+        if factor <= 1 {
+            return self.image;
+        }
+        let width = self.width() / factor;
+        let height = self.height() / factor;
+        let samples = (factor * factor) as f32;
+
+        RgbaImage::from_fn(width, height, |x, y| {
+            let mut sum = [0.0f32; 4];
+            for sy in 0..factor {
+                for sx in 0..factor {
+                    let pixel = self.image.get_pixel(x * factor + sx, y * factor + sy);
+                    let alpha = pixel[3] as f32;
+                    for c in 0..3 {
+                        sum[c] += pixel[c] as f32 * alpha;
+                    }
+                    sum[3] += alpha;
+                }
+            }
+            if sum[3] <= 0.0 {
+                return Rgba([0, 0, 0, 0]);
+            }
+            Rgba([
+                (sum[0] / sum[3]).round() as u8,
+                (sum[1] / sum[3]).round() as u8,
+                (sum[2] / sum[3]).round() as u8,
+                (sum[3] / samples).round() as u8,
+            ])
+        })
+    }
+}
+
+/// Visit the pixels of a `width` x `height` image covered by a segment with
+/// round caps, with their coverage
+fn line_coverage(
+    width: u32,
+    height: u32,
+    from: (f32, f32),
+    to: (f32, f32),
+    line_width: f32,
+    mut visit: impl FnMut(i64, i64, f32),
+) {
+    if ![from.0, from.1, to.0, to.1, line_width]
+        .iter()
+        .all(|v| v.is_finite())
+    {
+        return;
+    }
+    let radius = line_width / 2.0;
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length_sqr = dx * dx + dy * dy;
+    let coverage = |px: f32, py: f32| {
+        let t = if length_sqr > 0.0 {
+            (((px - from.0) * dx + (py - from.1) * dy) / length_sqr).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (ex, ey) = (from.0 + t * dx - px, from.1 + t * dy - py);
+        radius + 0.5 - (ex * ex + ey * ey).sqrt()
+    };
+
+    // Walk the major axis, visiting the few pixels across the line in each
+    // column (or row)
+    let steep = dy.abs() > dx.abs();
+    let (u0, v0, u1, v1) = if steep {
+        (from.1, from.0, to.1, to.0)
+    } else {
+        (from.0, from.1, to.0, to.1)
+    };
+    let (u_limit, v_limit) = if steep {
+        (height as f32, width as f32)
+    } else {
+        (width as f32, height as f32)
+    };
+    let slope = if u1 != u0 { (v1 - v0) / (u1 - u0) } else { 0.0 };
+    let half_span = radius * (1.0 + slope * slope).sqrt() + 1.0;
+    let (u_min, u_max) = (u0.min(u1), u0.max(u1));
+
+    let first = (u_min - radius - 1.0).floor().max(0.0) as i64;
+    let last = (u_max + radius + 1.0).ceil().min(u_limit - 1.0) as i64;
+    for u in first..=last {
+        let center = u as f32 + 0.5;
+        let v_center = v0 + (center.clamp(u_min, u_max) - u0) * slope;
+        let v_first = (v_center - half_span).floor().max(0.0) as i64;
+        let v_last = (v_center + half_span).ceil().min(v_limit - 1.0) as i64;
+        for v in v_first..=v_last {
+            let (x, y) = if steep { (v, u) } else { (u, v) };
+            let c = coverage(x as f32 + 0.5, y as f32 + 0.5);
+            if c > 0.0 {
+                visit(x, y, c);
+            }
+        }
+    }
+}
+
+/// Draw the epicycles at `time` and the `trail` behind the pen (oldest point
+/// first) into a `width` x `height` image
+pub fn render_epicycles(
+    epicycles: &Epicycles,
+    time: f32,
+    trail: &[(f32, f32)],
+    transform: &Transform,
+    width: u32,
+    height: u32,
+    style: &RasterStyle,
+) -> RgbaImage {
+    let factor = style.supersampling.max(1);
+    let scale = factor as f32;
+    let transform = Transform {
+        scale: transform.scale * scale,
+        offset: (transform.offset.0 * scale, transform.offset.1 * scale),
+    };
+    let theme = &style.theme;
+    let mut canvas = Canvas::new(width * factor, height * factor, theme.background.into());
+    let joints: Vec<(f32, f32)> = epicycles
+        .joints(time)
+        .into_iter()
+        .map(|joint| transform.apply(joint))
+        .collect();
+
+    if style.circles {
+        for (joint, arm) in joints.iter().zip(epicycles.arms()) {
+            let radius = arm.radius.abs() * transform.scale;
+            // Orbits smaller than a pixel only add noise around the pen
+            if radius >= scale {
+                canvas.circle(
+                    *joint,
+                    radius,
+                    theme.circle_width * scale,
+                    theme.circle_color(arm.frequency).into(),
+                );
+            }
+        }
+    }
+
+    let trail: Vec<(f32, f32)> = trail.iter().map(|p| transform.apply(*p)).collect();
+    canvas.polyline(&trail, theme.trail_width * scale, |position| {
+        let mut color: Rgba<u8> = theme.trail_color(position).into();
+        if style.fade_trail {
+            color[3] = (color[3] as f32 * position).round() as u8;
+        }
+        color
+    });
+
+    let arm_width = theme.arm_width * scale;
+    for (pair, arm) in joints.windows(2).zip(epicycles.arms()) {
+        let (from, to) = (pair[0], pair[1]);
+        let color = theme.arm_color(arm.frequency).into();
+        canvas.line(from, to, arm_width, color);
+
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if style.arrowheads && length >= 4.0 * scale {
+            let head = (arm_width * 3.0 + 3.0 * scale).min(length * 0.4);
+            let (ux, uy) = (dx / length, dy / length);
+            let base = (to.0 - ux * head, to.1 - uy * head);
+            let (nx, ny) = (-uy * head * 0.4, ux * head * 0.4);
+            canvas.triangle(
+                [to, (base.0 + nx, base.1 + ny), (base.0 - nx, base.1 - ny)],
+                color,
+            );
+        }
+    }
+
+    canvas.downsample(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DrawData;
+
+    #[test]
+    fn test_lines_are_anti_aliased() {
+        let white = Rgba([255, 255, 255, 255]);
+        let mut canvas = Canvas::new(20, 20, white);
+        // Horizontal line centered between two pixel rows
+        canvas.line((2.0, 10.0), (18.0, 10.0), 1.0, Rgba([0, 0, 0, 255]));
+        let image = canvas.into_image();
+
+        let above = image.get_pixel(10, 9)[0];
+        let below = image.get_pixel(10, 10)[0];
+        assert!(above > 100 && above < 155, "{}", above);
+        assert!(below > 100 && below < 155, "{}", below);
+        assert_eq!(*image.get_pixel(10, 5), white);
+    }
+
+    #[test]
+    fn test_supersampled_frame_has_output_size() {
+        let epicycles = Epicycles::new(&[DrawData::new(1.0, 10.0, 0.0)]);
+        let transform = Transform {
+            scale: 2.0,
+            offset: (32.0, 24.0),
+        };
+        let trail = vec![(0.0, 10.0), (10.0, 0.0)];
+        let style = RasterStyle::default().with_supersampling(3);
+        let image = render_epicycles(&epicycles, 0.0, &trail, &transform, 64, 48, &style);
+
+        assert_eq!(image.dimensions(), (64, 48));
+        // The arm runs from the center to the right
+        assert!(image.get_pixel(44, 24)[1] < 200);
+        assert_eq!(image.get_pixel(2, 2)[1], 255);
+    }
+}
@@ -0,0 +1,269 @@
+//! Symmetry analysis - Detect and enforce symmetries from the spectrum
+//!
+//! Rotating a closed curve by `2 PI / n` about its centroid is the same as
+//! shifting its start point by `1 / n` only if every non-DC frequency `k`
+//! satisfies `k = 1 (mod n)` (or `k = -1 (mod n)` when traced the other
+//! way). A mirror symmetry about an axis at angle `theta` through the
+//! centroid, with the reflected curve traced backwards from parameter `t0`,
+//! requires `X_k = e^(2 i theta) * conj(X_k) * e^(-2 PI i k t0)` for all `k != 0`.
+//!
+//! Confidences compare the energy that breaks a symmetry with the energy of
+//! the coefficients that could break it: the dominant one always fits a
+//! rotational symmetry and the two largest ones always fit some mirror axis.
+
+use std::f32::consts::PI;
+
+use rustfft::num_complex::Complex;
+use serde::{Deserialize, Serialize};
+
+use crate::DrawData;
+
+/// Number of candidate start points tried when searching for a mirror axis
+const MIRROR_PHASE_STEPS: usize = 2048;
+
+/// Default largest rotational order tested by `detect_symmetries`
+pub const DEFAULT_MAX_SYMMETRY_ORDER: usize = 12;
+
+/// Default confidence above which a symmetry is reported
+pub const DEFAULT_SYMMETRY_THRESHOLD: f32 = 0.9;
+
+/// A symmetry of a closed curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Symmetry {
+    /// Invariant under rotation by `2 PI / order` about the centroid
+    Rotational { order: usize },
+    /// Invariant under reflection about the line through the centroid at
+    /// `axis_angle` radians, the reflected curve starting at `phase_shift`
+    Mirror { axis_angle: f32, phase_shift: f32 },
+}
+
+/// A symmetry found in a shape, with a confidence between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DetectedSymmetry {
+    pub symmetry: Symmetry,
+    pub confidence: f32,
+}
+
+/// Non-DC coefficients of a shape, merged by frequency
+fn spectrum(data: &[DrawData]) -> Vec<(i32, Complex<f32>)> {
+    let mut spectrum: Vec<(i32, Complex<f32>)> = Vec::new();
+    for d in data {
+        let k = d.frequency.round() as i32;
+        if k == 0 {
+            continue;
+        }
+        let value = Complex::from_polar(d.radius, d.angle);
+        match spectrum.iter_mut().find(|(f, _)| *f == k) {
+            Some((_, c)) => *c += value,
+            None => spectrum.push((k, value)),
+        }
+    }
+    spectrum
+}
+
+/// Total energy and energy of every coefficient but the `skip` largest ones
+fn energies(spectrum: &[(i32, Complex<f32>)], skip: usize) -> (f32, f32) {
+    let mut energy: Vec<f32> = spectrum.iter().map(|(_, c)| c.norm_sqr()).collect();
+    energy.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let total = energy.iter().sum();
+    let rest = energy.iter().skip(skip).sum();
+    (total, rest)
+}
+
+/// Frequencies `k` with `k = residue (mod order)` are allowed by an
+/// `order`-fold rotational symmetry
+fn conforms(k: i32, order: usize, residue: i32) -> bool {
+    (k - residue).rem_euclid(order as i32) == 0
+}
+
+/// Share of energy breaking a symmetry turned into a confidence
+fn confidence_from_broken(broken: f32, total: f32, rest: f32) -> f32 {
+    if rest <= 1e-6 * total {
+        1.0
+    } else {
+        (1.0 - broken / rest).clamp(0.0, 1.0)
+    }
+}
+
+/// Residue (1 or -1) best matching an `order`-fold symmetry, with its confidence
+fn best_residue(spectrum: &[(i32, Complex<f32>)], order: usize) -> (i32, f32) {
+    let (total, rest) = energies(spectrum, 1);
+
+    [1, -1]
+        .into_iter()
+        .map(|residue| {
+            let broken: f32 = spectrum
+                .iter()
+                .filter(|(k, _)| !conforms(*k, order, residue))
+                .map(|(_, c)| c.norm_sqr())
+                .sum();
+            (residue, confidence_from_broken(broken, total, rest))
+        })
+        .fold((1, -1.0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/// Confidence that a shape has `order`-fold rotational symmetry
+pub fn rotational_symmetry_confidence(data: &[DrawData], order: usize) -> f32 {
+    if order < 2 {
+        return 1.0;
+    }
+    best_residue(&spectrum(data), order).1
+}
+
+/// Best mirror axis of a shape and the confidence that it is a mirror symmetry
+pub fn mirror_symmetry(data: &[DrawData]) -> DetectedSymmetry {
+    let spectrum = spectrum(data);
+    // An axis angle and a start point can always be found to fit two
+    // coefficients, so only the others are evidence of a mirror symmetry
+    let (total, rest) = energies(&spectrum, 2);
+
+    // |S(t0)| with S(t0) = sum |X_k|^2 e^(i (2 phi_k + 2 PI k t0)) measures how
+    // well a single axis fits every coefficient for that start point
+    let mut best = (0.0, 0.0, 0.0);
+    for step in 0..MIRROR_PHASE_STEPS {
+        let phase_shift = step as f32 / MIRROR_PHASE_STEPS as f32;
+        let s: Complex<f32> = spectrum
+            .iter()
+            .map(|(k, c)| {
+                Complex::from_polar(
+                    c.norm_sqr(),
+                    2.0 * c.arg() + 2.0 * PI * *k as f32 * phase_shift,
+                )
+            })
+            .sum();
+        if s.norm() > best.0 {
+            best = (s.norm(), s.arg() / 2.0, phase_shift);
+        }
+    }
+
+    let (fit, axis_angle, phase_shift) = best;
+    let confidence = confidence_from_broken(total - fit, total, rest);
+
+    DetectedSymmetry {
+        symmetry: Symmetry::Mirror {
+            axis_angle,
+            phase_shift,
+        },
+        confidence,
+    }
+}
+
+/// Report the symmetries of a shape with a confidence of at least `threshold`,
+/// most confident first. Rotational orders from 2 to `max_order` are tested.
+pub fn detect_symmetries(
+    data: &[DrawData],
+    max_order: usize,
+    threshold: f32,
+) -> Vec<DetectedSymmetry> {
+    let mut detected: Vec<DetectedSymmetry> = (2..=max_order)
+        .map(|order| DetectedSymmetry {
+            symmetry: Symmetry::Rotational { order },
+            confidence: rotational_symmetry_confidence(data, order),
+        })
+        .chain(std::iter::once(mirror_symmetry(data)))
+        .filter(|d| d.confidence >= threshold)
+        .collect();
+
+    detected.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    detected
+}
+
+/// Force a symmetry onto a shape
+///
+/// For a rotational symmetry, coefficients at non-conforming frequencies are
+/// zeroed. For a mirror symmetry, each coefficient is replaced by the average
+/// of itself and its mirror image. The DC term is left untouched.
+pub fn enforce_symmetry(data: &[DrawData], symmetry: &Symmetry) -> Vec<DrawData> {
+    match *symmetry {
+        Symmetry::Rotational { order } => {
+            if order < 2 {
+                return data.to_vec();
+            }
+            let (residue, _) = best_residue(&spectrum(data), order);
+            data.iter()
+                .map(|d| {
+                    let k = d.frequency.round() as i32;
+                    if k == 0 || conforms(k, order, residue) {
+                        d.clone()
+                    } else {
+                        DrawData::new(d.frequency, 0.0, d.angle)
+                    }
+                })
+                .collect()
+        }
+        Symmetry::Mirror {
+            axis_angle,
+            phase_shift,
+        } => data
+            .iter()
+            .map(|d| {
+                if d.frequency.round() as i32 == 0 {
+                    return d.clone();
+                }
+                let c = Complex::from_polar(d.radius, d.angle);
+                let mirrored = Complex::from_polar(
+                    1.0,
+                    2.0 * axis_angle - 2.0 * PI * d.frequency * phase_shift,
+                ) * c.conj();
+                DrawData::new_from_complex(d.frequency, (c + mirrored) / 2.0)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_svg_path, FourierConfig};
+
+    #[test]
+    fn test_rotational_symmetry() {
+        let config = FourierConfig::new(2048, 65);
+        let square = process_svg_path("M 0 0 L 10 0 L 10 10 L 0 10 Z", &config);
+        let triangle = process_svg_path("M 0 0 L 10 0 L 5 8.660254 Z", &config);
+
+        assert!(rotational_symmetry_confidence(&square, 4) > 0.99);
+        assert!(rotational_symmetry_confidence(&square, 2) > 0.99);
+        assert!(rotational_symmetry_confidence(&triangle, 3) > 0.99);
+        assert!(rotational_symmetry_confidence(&triangle, 2) < 0.5);
+    }
+
+    #[test]
+    fn test_mirror_symmetry() {
+        let config = FourierConfig::new(2048, 65);
+        let kite = process_svg_path("M 0 0 L 4 3 L 0 12 L -4 3 Z", &config);
+        let scalene = process_svg_path("M 0 0 L 14 0 L 3 5 Z", &config);
+
+        let detected = mirror_symmetry(&kite);
+        assert!(detected.confidence > 0.99);
+        if let Symmetry::Mirror { axis_angle, .. } = detected.symmetry {
+            // Vertical axis
+            assert!(axis_angle.cos().abs() < 0.01);
+        }
+        assert!(mirror_symmetry(&scalene).confidence < 0.9);
+    }
+
+    #[test]
+    fn test_enforce_symmetry() {
+        let config = FourierConfig::new(2048, 65);
+        let scalene = process_svg_path("M 0 0 L 14 0 L 3 5 Z", &config);
+
+        let rotational = enforce_symmetry(&scalene, &Symmetry::Rotational { order: 3 });
+        assert_eq!(rotational.len(), scalene.len());
+        assert!(rotational_symmetry_confidence(&rotational, 3) > 0.999);
+
+        let mirror = enforce_symmetry(&scalene, &mirror_symmetry(&scalene).symmetry);
+        assert!(mirror_symmetry(&mirror).confidence > 0.99);
+    }
+}
@@ -0,0 +1,307 @@
+//! Themes - Colors and line widths shared by every visualizer
+//!
+//! A theme can be one of the built-in presets or loaded from a JSON or TOML
+//! file. Colors are written as `#rrggbb`, `#rrggbbaa` or `transparent`, and
+//! every field is optional: missing ones are taken from the classic theme.
+//!
+//! ```toml
+//! background = "#101018"
+//! arm = "#f0a050"
+//! trail_gradient = ["#3050ff", "#ff40a0"]
+//! trail_width = 2.0
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use image::Rgba;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Names of the built-in themes, the first one is the default
+pub const THEME_PRESETS: &[&str] = &["classic", "dark", "blueprint", "rainbow", "transparent"];
+
+/// RGBA color written as a hex string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.a == 0
+    }
+
+    /// Linear interpolation between two colors, `t` in `[0, 1]`
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+}
+
+impl From<Color> for Rgba<u8> {
+    fn from(color: Color) -> Self {
+        Rgba([color.r, color.g, color.b, color.a])
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_transparent() {
+            write!(f, "transparent")
+        } else if self.a == 255 {
+            write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            write!(
+                f,
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.r, self.g, self.b, self.a
+            )
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("transparent") {
+            return Ok(Color::TRANSPARENT);
+        }
+        let hex = s
+            .strip_prefix('#')
+            .ok_or_else(|| format!("Invalid color '{}': expected #rrggbb or #rrggbbaa", s))?;
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("Invalid color '{}'", s))
+        };
+        match hex.len() {
+            6 => Ok(Color::rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Ok(Color::rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => Err(format!(
+                "Invalid color '{}': expected #rrggbb or #rrggbbaa",
+                s
+            )),
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Colors and line widths of a visualization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub background: Color,
+    /// Color of the arms, unless a palette is set
+    pub arm: Color,
+    /// Color of the orbit circles, unless a palette is set
+    pub circle: Color,
+    /// Color of the trail, unless a gradient is set
+    pub trail: Color,
+    /// Trail colors from its oldest point to the pen
+    pub trail_gradient: Vec<Color>,
+    /// Arm colors cycled by frequency, the orbit circles using them translucent
+    pub palette: Vec<Color>,
+    /// Line widths in pixels
+    pub trail_width: f32,
+    pub arm_width: f32,
+    pub circle_width: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::rgb(255, 255, 255),
+            arm: Color::rgb(202, 126, 86),
+            circle: Color::rgba(202, 126, 86, 80),
+            trail: Color::rgb(0, 0, 0),
+            trail_gradient: Vec::new(),
+            palette: Vec::new(),
+            trail_width: 1.5,
+            arm_width: 1.0,
+            circle_width: 1.0,
+        }
+    }
+}
+
+impl Theme {
+    /// Built-in theme by name, see `THEME_PRESETS`
+    pub fn preset(name: &str) -> Option<Theme> {
+        let classic = Theme::default();
+        match name {
+            "classic" => Some(classic),
+            "dark" => Some(Theme {
+                background: Color::rgb(18, 18, 24),
+                arm: Color::rgb(240, 160, 80),
+                circle: Color::rgba(240, 160, 80, 70),
+                trail: Color::rgb(240, 240, 240),
+                ..classic
+            }),
+            "blueprint" => Some(Theme {
+                background: Color::rgb(16, 58, 110),
+                arm: Color::rgb(255, 255, 255),
+                circle: Color::rgba(255, 255, 255, 60),
+                trail: Color::rgb(255, 255, 255),
+                trail_width: 2.0,
+                ..classic
+            }),
+            "rainbow" => Some(Theme {
+                background: Color::rgb(12, 12, 16),
+                trail_gradient: vec![Color::rgb(64, 96, 255), Color::rgb(255, 64, 160)],
+                palette: vec![
+                    Color::rgb(255, 80, 80),
+                    Color::rgb(255, 170, 60),
+                    Color::rgb(250, 230, 80),
+                    Color::rgb(90, 220, 110),
+                    Color::rgb(70, 180, 255),
+                    Color::rgb(160, 110, 255),
+                ],
+                trail_width: 2.0,
+                ..classic
+            }),
+            "transparent" => Some(Theme {
+                background: Color::TRANSPARENT,
+                ..classic
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn from_json(content: &str) -> Result<Theme, String> {
+        serde_json::from_str(content).map_err(|e| format!("Invalid JSON theme: {}", e))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Theme, String> {
+        toml::from_str(content).map_err(|e| format!("Invalid TOML theme: {}", e))
+    }
+
+    /// Load a theme file, TOML if it has a `.toml` extension and JSON otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Theme, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read theme {}: {}", path.display(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Theme::from_toml(&content),
+            _ => Theme::from_json(&content),
+        }
+    }
+
+    /// A preset name or the path of a theme file
+    pub fn resolve(name_or_path: &str) -> Result<Theme, String> {
+        match Theme::preset(name_or_path) {
+            Some(theme) => Ok(theme),
+            None if Path::new(name_or_path).is_file() => Theme::load(name_or_path),
+            None => Err(format!(
+                "Unknown theme '{}', expected one of {} or a theme file",
+                name_or_path,
+                THEME_PRESETS.join(", ")
+            )),
+        }
+    }
+
+    /// Color of the arm rotating at `frequency`
+    pub fn arm_color(&self, frequency: f32) -> Color {
+        self.palette_color(frequency).unwrap_or(self.arm)
+    }
+
+    /// Color of the orbit of the arm rotating at `frequency`
+    pub fn circle_color(&self, frequency: f32) -> Color {
+        match self.palette_color(frequency) {
+            Some(color) => Color {
+                a: self.circle.a,
+                ..color
+            },
+            None => self.circle,
+        }
+    }
+
+    /// Color of the trail at `position`, 0 at its oldest point and 1 at the pen
+    pub fn trail_color(&self, position: f32) -> Color {
+        match self.trail_gradient.len() {
+            0 => self.trail,
+            1 => self.trail_gradient[0],
+            n => {
+                let scaled = position.clamp(0.0, 1.0) * (n - 1) as f32;
+                let i = (scaled.floor() as usize).min(n - 2);
+                self.trail_gradient[i].lerp(&self.trail_gradient[i + 1], scaled - i as f32)
+            }
+        }
+    }
+
+    /// Frequencies `k` and `-k` share a color, so matching pairs of arms stand out
+    fn palette_color(&self, frequency: f32) -> Option<Color> {
+        if self.palette.is_empty() {
+            return None;
+        }
+        let k = (frequency.abs().round() as usize).saturating_sub(1);
+        Some(self.palette[k % self.palette.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_round_trip() {
+        for s in ["#ca7e56", "#10203040", "transparent"] {
+            let color: Color = s.parse().unwrap();
+            assert_eq!(color.to_string(), s);
+        }
+        assert!("ca7e56".parse::<Color>().is_err());
+        assert!("#12345".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn test_partial_theme_files() {
+        let json = Theme::from_json(r##"{"background": "transparent", "arm_width": 2}"##).unwrap();
+        assert!(json.background.is_transparent());
+        assert_eq!(json.arm_width, 2.0);
+        assert_eq!(json.trail, Theme::default().trail);
+
+        let toml = Theme::from_toml("trail_gradient = [\"#000000\", \"#ffffff\"]").unwrap();
+        assert_eq!(toml.trail_color(0.5), Color::rgb(128, 128, 128));
+
+        for name in THEME_PRESETS {
+            assert!(Theme::preset(name).is_some());
+        }
+    }
+}
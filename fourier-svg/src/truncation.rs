@@ -0,0 +1,143 @@
+//! Truncation - Draw a shape with fewer terms of its series
+//!
+//! Cutting a Fourier series after a few terms makes it ring around sharp
+//! corners (the Gibbs phenomenon). A window scales the kept terms down towards
+//! the cutoff frequency, trading the ringing for softer corners:
+//!
+//! - Fejér: `1 - |k| / c`, the average of the partial sums
+//! - Lanczos: `sinc(|k| / c)`, the sigma factors
+//! - Hann: `(1 + cos(PI |k| / c)) / 2`
+//!
+//! where `c` is one more than the highest kept frequency.
+
+use std::f32::consts::PI;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::DrawData;
+
+/// Which rotating terms are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncation {
+    /// The lowest frequencies, in the order of the data
+    LowestFrequencies,
+    /// The largest radii, chained from the largest one
+    LargestRadii,
+}
+
+/// Weights tapering the kept terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Window {
+    /// Keep the terms as they are
+    Rectangular,
+    /// `1 - |k| / c`, the average of the partial sums
+    Fejer,
+    /// `sinc(|k| / c)`, the sigma factors
+    Lanczos,
+    /// `(1 + cos(PI |k| / c)) / 2`
+    Hann,
+}
+
+impl Window {
+    /// Weight of a term of `frequency` with the given cutoff frequency
+    pub fn weight(self, frequency: f32, cutoff: f32) -> f32 {
+        let x = (frequency.abs() / cutoff.max(f32::EPSILON)).min(1.0);
+        match self {
+            Window::Rectangular => 1.0,
+            Window::Fejer => 1.0 - x,
+            Window::Lanczos if x == 0.0 => 1.0,
+            Window::Lanczos => (PI * x).sin() / (PI * x),
+            Window::Hann => 0.5 * (1.0 + (PI * x).cos()),
+        }
+    }
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Truncation::LowestFrequencies => "lowest frequencies",
+            Truncation::LargestRadii => "largest radii",
+        })
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Window::Rectangular => "rectangular",
+            Window::Fejer => "Fejér",
+            Window::Lanczos => "Lanczos",
+            Window::Hann => "Hann",
+        })
+    }
+}
+
+/// Keep `terms` rotating terms of `data` and taper them with `window`.
+/// DC terms are always kept, as they only place the drawing.
+pub fn truncate(
+    data: &[DrawData],
+    terms: usize,
+    truncation: Truncation,
+    window: Window,
+) -> Vec<DrawData> {
+    let (mut kept, mut arms): (Vec<DrawData>, Vec<DrawData>) =
+        data.iter().cloned().partition(|d| d.frequency == 0.0);
+
+    match truncation {
+        Truncation::LowestFrequencies => {
+            // Stable, so terms of the same frequency keep their order
+            arms.sort_by(|a, b| a.frequency.abs().total_cmp(&b.frequency.abs()));
+        }
+        Truncation::LargestRadii => {
+            arms.sort_by(|a, b| b.radius.abs().total_cmp(&a.radius.abs()));
+        }
+    }
+    arms.truncate(terms);
+
+    let cutoff = arms.iter().map(|d| d.frequency.abs()).fold(0.0, f32::max) + 1.0;
+    kept.extend(arms.into_iter().map(|d| DrawData {
+        radius: d.radius * window.weight(d.frequency, cutoff),
+        ..d
+    }));
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_and_taper() {
+        let data = vec![
+            DrawData::new(0.0, 7.0, 0.0),
+            DrawData::new(1.0, 10.0, 0.0),
+            DrawData::new(-1.0, 1.0, 0.0),
+            DrawData::new(2.0, 0.5, 0.0),
+            DrawData::new(-2.0, 4.0, 0.0),
+        ];
+        let frequencies =
+            |data: &[DrawData]| -> Vec<f32> { data.iter().map(|d| d.frequency).collect() };
+
+        let lowest = truncate(&data, 3, Truncation::LowestFrequencies, Window::Rectangular);
+        assert_eq!(frequencies(&lowest), vec![0.0, 1.0, -1.0, 2.0]);
+        assert_eq!(lowest[3].radius, 0.5);
+
+        let largest = truncate(&data, 2, Truncation::LargestRadii, Window::Rectangular);
+        assert_eq!(frequencies(&largest), vec![0.0, 1.0, -2.0]);
+
+        // The cutoff is 3 with frequencies up to 2 kept, the DC term untouched
+        let fejer = truncate(&data, 2, Truncation::LargestRadii, Window::Fejer);
+        assert_eq!(fejer[0].radius, 7.0);
+        assert!((fejer[1].radius - 10.0 * 2.0 / 3.0).abs() < 1e-5);
+        assert!((fejer[2].radius - 4.0 / 3.0).abs() < 1e-5);
+
+        for window in [Window::Lanczos, Window::Hann] {
+            assert_eq!(window.weight(0.0, 3.0), 1.0);
+            assert!(window.weight(1.0, 3.0) > window.weight(2.0, 3.0));
+            assert!(window.weight(3.0, 3.0).abs() < 1e-6);
+        }
+    }
+}
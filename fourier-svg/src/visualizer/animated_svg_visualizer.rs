@@ -0,0 +1,287 @@
+//! Animated SVG - Epicycle animation without JavaScript
+//!
+//! The animation is declared with SMIL, so it also plays where scripts are
+//! not run, e.g. in an `<img>` tag or a README. Every arm is a group nested in
+//! the previous one and rotating with `animateTransform`: as rotations add up
+//! along the chain, each group turns by the difference between its frequency
+//! and its parent's. The trace is revealed by animating its
+//! `stroke-dashoffset` in step with the pen.
+
+use std::f32::consts::PI;
+use std::io::Write;
+
+use svg::node::element::{Animate, AnimateTransform, Circle, Group, Line, Path, Rectangle};
+use svg::Document;
+
+use crate::epicycles::Epicycles;
+use crate::fft_drawer::DrawData;
+use crate::framing::{Framing, Transform, BOUNDS_SAMPLES};
+use crate::theme::Theme;
+use crate::visualizer::svg_visualizer::{number, path_data};
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Points of the trace over one period
+const TRACE_SAMPLES: usize = 1024;
+
+/// Instants at which the revealed length of the trace is given
+const KEY_TIMES: usize = 100;
+
+pub struct AnimatedSVGVisualizer {
+    file_name: String,
+    width: u32,
+    height: u32,
+    duration: f32,
+    terms: Option<usize>,
+    framing: Framing,
+    theme: Theme,
+}
+
+impl AnimatedSVGVisualizer {
+    pub fn new(file_name: String) -> AnimatedSVGVisualizer {
+        AnimatedSVGVisualizer {
+            file_name,
+            width: 800,
+            height: 600,
+            duration: 8.0,
+            terms: None,
+            framing: Framing::default(),
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Seconds taken to draw one period
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Only animate the first `terms` circles, keeping the file small
+    pub fn with_terms(mut self, terms: usize) -> Self {
+        self.terms = Some(terms);
+        self
+    }
+
+    /// Control how the drawing is scaled and placed in the image
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Nested rotating groups of the arms, in pixels from the anchor
+    fn arms(&self, epicycles: &Epicycles, transform: &Transform) -> Group {
+        let theme = &self.theme;
+        let duration = format!("{}s", number(self.duration));
+
+        // Built from the last arm outwards, each arm holding the next one
+        let mut inner: Option<Group> = None;
+        let arms = epicycles.arms();
+        for (i, arm) in arms.iter().enumerate().rev() {
+            let (parent_angle, parent_frequency) = match i {
+                0 => (0.0, 0.0),
+                _ => (arms[i - 1].angle, arms[i - 1].frequency),
+            };
+            let angle = (arm.angle - parent_angle) * 180.0 / PI;
+            let turns = arm.frequency - parent_frequency;
+            let radius = arm.radius * transform.scale;
+
+            let mut group = Group::new()
+                .set("transform", format!("rotate({})", number(angle)))
+                .add(
+                    AnimateTransform::new()
+                        .set("attributeName", "transform")
+                        .set("type", "rotate")
+                        .set("from", number(angle))
+                        .set("to", number(angle + 360.0 * turns))
+                        .set("dur", duration.as_str())
+                        .set("repeatCount", "indefinite"),
+                );
+            // Orbits smaller than a pixel would only add clutter
+            if radius >= 1.0 {
+                group = group.add(
+                    Circle::new()
+                        .set("r", number(radius))
+                        .set("stroke", theme.circle_color(arm.frequency).to_string())
+                        .set("stroke-width", theme.circle_width),
+                );
+            }
+            group = group.add(
+                Line::new()
+                    .set("x2", number(radius))
+                    .set("stroke", theme.arm_color(arm.frequency).to_string())
+                    .set("stroke-width", theme.arm_width),
+            );
+            if let Some(next) = inner.take() {
+                group = group.add(
+                    Group::new()
+                        .set("transform", format!("translate({} 0)", number(radius)))
+                        .add(next),
+                );
+            }
+            inner = Some(group);
+        }
+
+        let (x, y) = transform.apply(epicycles.center());
+        let mut anchor = Group::new().set(
+            "transform",
+            format!("translate({} {})", number(x), number(y)),
+        );
+        if let Some(arms) = inner {
+            anchor = anchor.add(arms);
+        }
+        anchor
+    }
+
+    /// Trace revealed as the pen moves, in pixels
+    fn trace(&self, epicycles: &Epicycles, transform: &Transform) -> Path {
+        let points: Vec<(f32, f32)> = epicycles
+            .trace(TRACE_SAMPLES)
+            .into_iter()
+            .map(|point| transform.apply(point))
+            .collect();
+
+        // Length drawn at each sample, the last step closing the path
+        let mut drawn = Vec::with_capacity(TRACE_SAMPLES + 1);
+        let mut length = 0.0;
+        drawn.push(0.0);
+        for i in 0..TRACE_SAMPLES {
+            let (a, b) = (points[i], points[(i + 1) % TRACE_SAMPLES]);
+            length += ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+            drawn.push(length);
+        }
+        let length = length.max(f32::EPSILON);
+
+        let key_times: Vec<String> = (0..=KEY_TIMES)
+            .map(|k| number(k as f32 / KEY_TIMES as f32))
+            .collect();
+        let offsets: Vec<String> = (0..=KEY_TIMES)
+            .map(|k| number(length - drawn[k * TRACE_SAMPLES / KEY_TIMES]))
+            .collect();
+
+        Path::new()
+            .set("d", path_data(&points))
+            .set("pathLength", number(length))
+            .set("stroke", self.theme.trail_color(1.0).to_string())
+            .set("stroke-width", self.theme.trail_width)
+            .set("stroke-dasharray", number(length))
+            .set("stroke-dashoffset", number(length))
+            .add(
+                Animate::new()
+                    .set("attributeName", "stroke-dashoffset")
+                    .set("values", offsets.join(";"))
+                    .set("keyTimes", key_times.join(";"))
+                    .set("dur", format!("{}s", number(self.duration)))
+                    .set("repeatCount", "indefinite"),
+            )
+    }
+}
+
+impl Default for AnimatedSVGVisualizer {
+    fn default() -> Self {
+        Self::new("output.svg".to_string())
+    }
+}
+
+impl Visualizer for AnimatedSVGVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        if self.width == 0 || self.height == 0 || self.duration.is_nan() || self.duration <= 0.0 {
+            return Err(VisualizerError::InvalidData(format!(
+                "cannot animate over {}s at {}x{} pixels",
+                self.duration, self.width, self.height
+            )));
+        }
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let mut epicycles = Epicycles::new(&data);
+        if let Some(terms) = self.terms {
+            epicycles = epicycles.truncated(terms);
+        }
+        let bounds = epicycles.bounds(BOUNDS_SAMPLES);
+        let transform = self
+            .framing
+            .transform(&bounds, self.width as f32, self.height as f32);
+
+        let mut document = Document::new()
+            .set("width", self.width)
+            .set("height", self.height)
+            .set("viewBox", (0, 0, self.width, self.height));
+        if !self.theme.background.is_transparent() {
+            document = document.add(
+                Rectangle::new()
+                    .set("width", "100%")
+                    .set("height", "100%")
+                    .set("fill", self.theme.background.to_string()),
+            );
+        }
+        document = document.add(
+            Group::new()
+                .set("fill", "none")
+                .set("stroke-linecap", "round")
+                .set("stroke-linejoin", "round")
+                .add(self.trace(&epicycles, &transform))
+                .add(self.arms(&epicycles, &transform)),
+        );
+
+        let mut writer = CountingWriter::new(writer);
+        svg::write(&mut writer, &document)?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arms_rotate_relative_to_their_parent() {
+        let data = vec![
+            DrawData::new(0.0, 5.0, 0.0),
+            DrawData::new(1.0, 100.0, 0.0),
+            DrawData::new(-1.0, 50.0, PI / 2.0),
+            DrawData::new(2.0, 0.1, 0.0),
+        ];
+        let visualizer = AnimatedSVGVisualizer::default()
+            .with_terms(2)
+            .with_duration(4.0);
+
+        let mut buffer = Vec::new();
+        visualizer.render_to(data, &mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+
+        assert!(!svg.contains("<script"));
+        assert_eq!(svg.matches("<animateTransform").count(), 2);
+        // The first arm turns once, the second one back twice relative to it
+        assert!(svg.contains(r#"from="0" repeatCount="indefinite" to="360""#));
+        assert!(svg.contains(r#"from="90" repeatCount="indefinite" to="-630""#));
+        assert!(svg.contains(r#"attributeName="stroke-dashoffset""#));
+        assert!(svg.contains(r#"dur="4s""#));
+    }
+}
@@ -0,0 +1,328 @@
+//! Animation - Timeline and frames shared by the animated raster visualizers
+//!
+//! The timeline is defined in full periods of the drawing, so the last frame
+//! leads back into the first one and a looping animation has no visible seam.
+//! Every frame is a pure function of its index: the trail behind the pen is
+//! recomputed from the time rather than accumulated from previous frames.
+
+use image::RgbaImage;
+
+use crate::epicycles::Epicycles;
+use crate::fft_drawer::DrawData;
+use crate::framing::{morph_bounds, Framing, Transform, BOUNDS_SAMPLES};
+use crate::morph::{Morph, MorphTarget};
+use crate::raster::{render_epicycles, RasterStyle};
+use crate::theme::Theme;
+use crate::visualizer::VisualizerError;
+
+/// Trail points sampled per period, independently of the frame count
+const TRAIL_SAMPLES: usize = 1024;
+
+/// Timeline and appearance of an animated raster visualization
+///
+/// The GIF, APNG, WebP, PNG sequence, Y4M and terminal backends all take one
+/// with `with_animation` and draw the same frames from it; they only differ
+/// in how the timing is stored.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    width: u32,
+    height: u32,
+    frames: usize,
+    cycles: usize,
+    hold_frames: usize,
+    trail: f32,
+    delay_ms: u32,
+    /// Exact frame rate, when set with `with_fps`
+    fps: Option<u32>,
+    morph: Option<MorphTarget>,
+    framing: Framing,
+    style: RasterStyle,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            frames: 100,
+            cycles: 1,
+            hold_frames: 0,
+            trail: 0.8,
+            delay_ms: 20,
+            fps: None,
+            morph: None,
+            framing: Framing::default(),
+            style: RasterStyle::default(),
+        }
+    }
+}
+
+impl Animation {
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Number of animated frames, spread evenly over every cycle
+    pub fn with_frames(mut self, frames: usize) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Number of full periods drawn by the animated frames
+    pub fn with_cycles(mut self, cycles: usize) -> Self {
+        self.cycles = cycles;
+        self
+    }
+
+    /// Frames showing the finished drawing after the last cycle.
+    ///
+    /// Without hold frames the trail wraps around the period and the
+    /// animation loops seamlessly. With hold frames the drawing is built up
+    /// from an empty canvas instead, then held complete before the loop
+    /// restarts.
+    pub fn with_hold_frames(mut self, hold_frames: usize) -> Self {
+        self.hold_frames = hold_frames;
+        self
+    }
+
+    /// Length of the trail behind the pen, as a fraction of a period
+    pub fn with_trail(mut self, trail: f32) -> Self {
+        self.trail = trail.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Time each frame is shown, in milliseconds
+    pub fn with_delay_ms(mut self, delay_ms: u32) -> Self {
+        self.delay_ms = delay_ms;
+        self.fps = None;
+        self
+    }
+
+    /// Frames shown per second, for outputs storing an exact frame rate.
+    /// Millisecond based outputs use the nearest delay.
+    pub fn with_fps(mut self, fps: u32) -> Self {
+        let fps = fps.max(1);
+        self.delay_ms = ((1000.0 / fps as f32).round() as u32).max(1);
+        self.fps = Some(fps);
+        self
+    }
+
+    /// Morph the rendered shape into `target` while the animation plays.
+    /// The animation is lengthened if needed so the morph can complete.
+    pub fn with_morph(mut self, target: MorphTarget) -> Self {
+        self.morph = Some(target);
+        self
+    }
+
+    /// Control how the drawing is scaled and placed in the frame
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Colors, line widths and supersampling of the frames
+    pub fn with_style(mut self, style: RasterStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Colors and line widths of the frames
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.style.theme = theme;
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn delay_ms(&self) -> u32 {
+        self.delay_ms
+    }
+
+    /// Frame rate as a `(numerator, denominator)` number of frames per second
+    pub fn frame_rate(&self) -> (u32, u32) {
+        match self.fps {
+            Some(fps) => (fps, 1),
+            None => (1000, self.delay_ms.max(1)),
+        }
+    }
+
+    /// Time since the start of the animation when `frame` is shown, in seconds
+    fn elapsed(&self, frame: usize) -> f32 {
+        let (numerator, denominator) = self.frame_rate();
+        frame as f32 * denominator as f32 / numerator as f32
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.style.theme
+    }
+
+    pub(crate) fn check(&self) -> Result<(), VisualizerError> {
+        if self.width == 0 || self.height == 0 || self.frames == 0 || self.cycles == 0 {
+            return Err(VisualizerError::InvalidData(format!(
+                "cannot render {} frames over {} cycles at {}x{} pixels",
+                self.frames, self.cycles, self.width, self.height
+            )));
+        }
+        Ok(())
+    }
+
+    /// Time of an animated frame, in periods since the start of the animation
+    fn frame_time(&self, frame: usize, frames: usize) -> f32 {
+        frame as f32 * self.cycles as f32 / frames as f32
+    }
+
+    /// Points drawn up to `time`, oldest first
+    ///
+    /// The trail only depends on `time` modulo one period, except when
+    /// building the drawing up for hold frames, where nothing is drawn
+    /// before the animation starts.
+    fn trail(&self, epicycles: &Epicycles, time: f32) -> Vec<(f32, f32)> {
+        let start = if self.hold_frames > 0 {
+            (time - 1.0).max(0.0)
+        } else {
+            time - self.trail
+        };
+        let steps = ((time - start) * TRAIL_SAMPLES as f32).ceil() as usize;
+        if steps == 0 {
+            return vec![epicycles.tip(time)];
+        }
+        (0..=steps)
+            .map(|i| epicycles.tip(start + (time - start) * i as f32 / steps as f32))
+            .collect()
+    }
+
+    /// Everything needed to render the frames of `data`
+    pub(crate) fn prepare(&self, data: &[DrawData]) -> PreparedAnimation<'_> {
+        let morph = self
+            .morph
+            .as_ref()
+            .map(|target| (Morph::new(data, &target.data, &target.options), target));
+        let frames = match &morph {
+            Some((_, target)) => {
+                let (numerator, denominator) = self.frame_rate();
+                let morph_frames = (target.duration * numerator as f32 / denominator as f32).ceil();
+                self.frames.max(morph_frames as usize)
+            }
+            None => self.frames,
+        };
+
+        let epicycles = Epicycles::new(data);
+
+        // Frame the whole animation, including every stage of a morph
+        let bounds = match &morph {
+            Some((morph, _)) => morph_bounds(morph, BOUNDS_SAMPLES),
+            None => epicycles.bounds(BOUNDS_SAMPLES),
+        };
+        let transform = self
+            .framing
+            .transform(&bounds, self.width as f32, self.height as f32);
+
+        // A drawing being built up is shown in full, without fading
+        let style = RasterStyle {
+            fade_trail: self.style.fade_trail && self.hold_frames == 0,
+            ..self.style.clone()
+        };
+
+        PreparedAnimation {
+            animation: self,
+            epicycles,
+            morph,
+            frames,
+            transform,
+            style,
+        }
+    }
+}
+
+/// An animation bound to the shape it draws
+pub(crate) struct PreparedAnimation<'a> {
+    animation: &'a Animation,
+    epicycles: Epicycles,
+    morph: Option<(Morph, &'a MorphTarget)>,
+    /// Animated frames, without the hold frames
+    frames: usize,
+    transform: Transform,
+    style: RasterStyle,
+}
+
+impl PreparedAnimation<'_> {
+    /// Number of frames including the hold frames
+    pub(crate) fn frame_count(&self) -> usize {
+        self.frames + self.animation.hold_frames
+    }
+
+    pub(crate) fn render(&self, frame: usize) -> RgbaImage {
+        let animation = self.animation;
+        let holding = frame >= self.frames;
+        // Hold frames show the state at the end of the last cycle, which is
+        // also where the first frame starts
+        let time = animation.frame_time(frame.min(self.frames), self.frames);
+
+        let morphed;
+        let epicycles = match &self.morph {
+            Some((morph, target)) => {
+                let elapsed = animation.elapsed(frame);
+                morphed = Epicycles::new(&morph.at(target.progress(elapsed)));
+                &morphed
+            }
+            None => &self.epicycles,
+        };
+
+        let wave = if holding {
+            let mut wave = epicycles.trace(TRAIL_SAMPLES);
+            wave.push(epicycles.tip(0.0));
+            wave
+        } else {
+            animation.trail(epicycles, time)
+        };
+
+        render_epicycles(
+            epicycles,
+            time,
+            &wave,
+            &self.transform,
+            animation.width,
+            animation.height,
+            &self.style,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_loops_over_full_periods() {
+        let animation = Animation::default().with_frames(10).with_cycles(2);
+        let data = vec![DrawData::new(1.0, 10.0, 0.0), DrawData::new(-2.0, 3.0, 1.0)];
+        let epicycles = Epicycles::new(&data);
+
+        // The frame after the last one is the first frame, one cycle later
+        let last = animation.frame_time(9, 10);
+        let next = animation.frame_time(10, 10);
+        assert!((next - 2.0).abs() < 1e-6);
+        assert!((last - 1.8).abs() < 1e-6);
+
+        // So the trail of the first frame continues the previous period
+        let looped = animation.trail(&epicycles, next);
+        let first = animation.trail(&epicycles, 0.0);
+        assert_eq!(looped.len(), first.len());
+        for (a, b) in looped.iter().zip(&first) {
+            assert!((a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3);
+        }
+
+        // Building up for hold frames starts from an empty drawing
+        let building = Animation::default().with_hold_frames(5);
+        assert_eq!(building.trail(&epicycles, 0.0).len(), 1);
+        assert_eq!(building.prepare(&data).frame_count(), 105);
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::Write;
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::animation::Animation;
+use crate::visualizer::frames::render_in_order;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Animated PNG with full RGBA frames and millisecond timing
+pub struct APNGVisualizer {
+    file_name: String,
+    animation: Animation,
+}
+
+impl APNGVisualizer {
+    pub fn new(file_name: String) -> APNGVisualizer {
+        APNGVisualizer {
+            file_name,
+            animation: Animation::default(),
+        }
+    }
+
+    /// Frames to draw, the delay exact when set as a frame rate
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+}
+
+impl Default for APNGVisualizer {
+    fn default() -> Self {
+        Self::new("output.png".to_string())
+    }
+}
+
+impl Visualizer for APNGVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let animation = self.animation.prepare(&data);
+        let frames = animation.frame_count();
+
+        let mut writer = CountingWriter::new(writer);
+        let mut encoder =
+            png::Encoder::new(&mut writer, self.animation.width(), self.animation.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // Loop forever
+        encoder.set_animated(frames as u32, 0)?;
+        // The delay is a fraction of a second, exact when the frame rate fits
+        let (delay_num, delay_den) = match self.animation.frame_rate() {
+            (fps, seconds) if fps <= u16::MAX as u32 && seconds <= u16::MAX as u32 => {
+                (seconds as u16, fps as u16)
+            }
+            _ => (self.animation.delay_ms().min(u16::MAX as u32) as u16, 1000),
+        };
+        encoder.set_frame_delay(delay_num, delay_den)?;
+
+        let mut png_writer = encoder.write_header()?;
+        render_in_order(
+            frames,
+            |frame| animation.render(frame).into_raw(),
+            |pixels| Ok(png_writer.write_image_data(&pixels)?),
+        )?;
+        png_writer.finish()?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::{render_test_data, test_data};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_apng_frames_and_delay() {
+        let animation = Animation::default()
+            .with_dimensions(20, 10)
+            .with_frames(3)
+            .with_delay_ms(40);
+        let visualizer = APNGVisualizer::default().with_animation(animation);
+        let (summary, buffer) = render_test_data(&visualizer);
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.bytes, buffer.len() as u64);
+
+        let mut reader = png::Decoder::new(Cursor::new(buffer)).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 0));
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        for _ in 0..3 {
+            reader.next_frame(&mut pixels).unwrap();
+            let frame = reader.info().frame_control.unwrap();
+            assert_eq!((frame.delay_num, frame.delay_den), (40, 1000));
+        }
+    }
+
+    /// With or without the `parallel` feature, the frames are the ones
+    /// rendered one by one, in order, so the encoded bytes are the same
+    #[test]
+    fn test_apng_frames_match_sequential_rendering() {
+        let animation = Animation::default().with_dimensions(24, 16).with_frames(16);
+        let visualizer = APNGVisualizer::default().with_animation(animation.clone());
+        let (_, buffer) = render_test_data(&visualizer);
+
+        let data = test_data();
+        let expected = animation.prepare(&data);
+        let mut reader = png::Decoder::new(Cursor::new(buffer)).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        for frame in 0..16 {
+            reader.next_frame(&mut pixels).unwrap();
+            assert_eq!(pixels, expected.render(frame).into_raw(), "frame {}", frame);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Array - Raw numbers as CSV or NumPy `.npy`, for analysis notebooks
+//!
+//! The coefficients come as rows of `frequency, re, im, radius, angle`, and
+//! the sample points of the path as rows of `x, y`. CSV starts with a header
+//! naming the columns. NPY holds a C-ordered 2D array of little-endian
+//! doubles (`<f8`), loaded with `numpy.load`.
+
+use std::io::Write;
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Layout of the numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayFormat {
+    Csv,
+    Npy,
+}
+
+impl ArrayFormat {
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArrayFormat::Csv => "csv",
+            ArrayFormat::Npy => "npy",
+        }
+    }
+}
+
+/// Numbers written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayContent {
+    /// `frequency, re, im, radius, angle` of every coefficient
+    Coefficients,
+    /// `x, y` of every point the path was sampled at
+    Samples,
+}
+
+pub struct ArrayVisualizer {
+    file_name: String,
+    format: ArrayFormat,
+    content: ArrayContent,
+    samples: Option<Vec<(f32, f32)>>,
+}
+
+impl ArrayVisualizer {
+    pub fn new(file_name: String) -> ArrayVisualizer {
+        ArrayVisualizer {
+            file_name,
+            format: ArrayFormat::Csv,
+            content: ArrayContent::Coefficients,
+            samples: None,
+        }
+    }
+
+    pub fn with_format(mut self, format: ArrayFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_content(mut self, content: ArrayContent) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Points the path was sampled at, written with `ArrayContent::Samples`
+    pub fn with_samples(mut self, samples: Vec<(f32, f32)>) -> Self {
+        self.samples = Some(samples);
+        self
+    }
+
+    /// Column names and rows of the array
+    fn table(&self, data: &[DrawData]) -> (&'static [&'static str], Vec<Vec<f32>>) {
+        match self.content {
+            ArrayContent::Coefficients => (
+                &["frequency", "re", "im", "radius", "angle"],
+                data.iter()
+                    .map(|d| {
+                        vec![
+                            d.frequency,
+                            d.radius * d.angle.cos(),
+                            d.radius * d.angle.sin(),
+                            d.radius,
+                            d.angle,
+                        ]
+                    })
+                    .collect(),
+            ),
+            ArrayContent::Samples => (
+                &["x", "y"],
+                self.samples
+                    .iter()
+                    .flatten()
+                    .map(|&(x, y)| vec![x, y])
+                    .collect(),
+            ),
+        }
+    }
+
+    fn csv(columns: &[&str], rows: &[Vec<f32>], writer: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(writer, "{}", columns.join(","))?;
+        for row in rows {
+            let values: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+            writeln!(writer, "{}", values.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// NPY format version 1.0: magic, header length, header dict padded so
+    /// the data is 64-byte aligned, then the values
+    fn npy(columns: usize, rows: &[Vec<f32>], writer: &mut dyn Write) -> std::io::Result<()> {
+        const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+        let mut header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+            rows.len(),
+            columns
+        );
+        // Magic, 2 bytes of length, the header and its newline
+        let unpadded = MAGIC.len() + 2 + header.len() + 1;
+        header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+        header.push('\n');
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for value in rows.iter().flatten() {
+            writer.write_all(&(*value as f64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ArrayVisualizer {
+    fn default() -> Self {
+        Self::new("output.csv".to_string())
+    }
+}
+
+impl Visualizer for ArrayVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        match self.content {
+            ArrayContent::Coefficients => check_data(data),
+            ArrayContent::Samples if self.samples.is_none() => Err(VisualizerError::InvalidData(
+                "no sample points, they come from an SVG path or an export including them"
+                    .to_string(),
+            )),
+            ArrayContent::Samples => Ok(()),
+        }
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let (columns, rows) = self.table(&data);
+        let mut writer = CountingWriter::new(writer);
+        match self.format {
+            ArrayFormat::Csv => Self::csv(columns, &rows, &mut writer)?,
+            ArrayFormat::Npy => Self::npy(columns.len(), &rows, &mut writer)?,
+        }
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coefficients_and_samples_as_csv_and_npy() {
+        let data = vec![
+            DrawData::new(0.0, 2.0, 0.0),
+            DrawData::new(-1.0, 4.0, std::f32::consts::PI / 2.0),
+        ];
+        let render = |visualizer: ArrayVisualizer| {
+            let mut buffer = Vec::new();
+            visualizer.render_to(data.clone(), &mut buffer).unwrap();
+            buffer
+        };
+
+        let csv = String::from_utf8(render(ArrayVisualizer::default())).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "frequency,re,im,radius,angle");
+        assert_eq!(lines[1], "0,2,0,2,0");
+        assert!(lines[2].starts_with("-1,-0.0000001") && lines[2].ends_with(",4,4,1.5707964"));
+
+        let samples = || ArrayVisualizer::default().with_content(ArrayContent::Samples);
+        assert!(samples().check(&data).is_err());
+        let csv = render(samples().with_samples(vec![(1.0, 2.5), (3.0, 4.0)]));
+        assert_eq!(String::from_utf8(csv).unwrap(), "x,y\n1,2.5\n3,4\n");
+
+        let npy = render(
+            samples()
+                .with_format(ArrayFormat::Npy)
+                .with_samples(vec![(1.0, 2.5), (3.0, 4.0)]),
+        );
+        assert!(npy.starts_with(b"\x93NUMPY\x01\x00"));
+        let header_length = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        let header = std::str::from_utf8(&npy[10..10 + header_length]).unwrap();
+        assert!(header.contains("'shape': (2, 2)") && header.ends_with('\n'));
+        assert_eq!((10 + header_length) % 64, 0);
+        assert_eq!(npy.len(), 10 + header_length + 4 * 8);
+        assert_eq!(npy[npy.len() - 8..], 4.0f64.to_le_bytes());
+    }
+}
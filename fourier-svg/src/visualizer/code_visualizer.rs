@@ -0,0 +1,305 @@
+//! Code - Source code evaluating the series, to paste into a program
+//!
+//! The terms are written as a constant array of `(frequency, radius, phase)`
+//! next to a function returning the point of the curve at `t` in `[0, 1)`:
+//! `eval` in Rust and Python, `evaluate` in JavaScript where `eval` cannot be
+//! declared, and `fourier_eval` in GLSL which has no namespaces.
+//!
+//! The DC terms are merged into a single term of frequency 0. With y pointing
+//! up, the curve is mirrored by conjugating the terms, so the generated
+//! function is the same for both orientations.
+
+use std::io::Write;
+
+use crate::epicycles::Epicycles;
+use crate::fft_drawer::DrawData;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Language of the generated source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Rust,
+    /// An ES module
+    JavaScript,
+    Python,
+    /// GLSL ES 3.00 / GLSL 3.30, with array constructors
+    Glsl,
+}
+
+impl CodeLanguage {
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "rs",
+            CodeLanguage::JavaScript => "js",
+            CodeLanguage::Python => "py",
+            CodeLanguage::Glsl => "glsl",
+        }
+    }
+}
+
+pub struct CodeVisualizer {
+    file_name: String,
+    language: CodeLanguage,
+    y_up: bool,
+    precision: usize,
+    terms: Option<usize>,
+}
+
+impl CodeVisualizer {
+    pub fn new(file_name: String) -> CodeVisualizer {
+        CodeVisualizer {
+            file_name,
+            language: CodeLanguage::Rust,
+            y_up: false,
+            precision: 4,
+            terms: None,
+        }
+    }
+
+    pub fn with_language(mut self, language: CodeLanguage) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Flip the curve for y pointing up, instead of down as in SVG
+    pub fn with_y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// Decimals written for every number
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Only write the first `terms` rotating terms
+    pub fn with_terms(mut self, terms: usize) -> Self {
+        self.terms = Some(terms);
+        self
+    }
+
+    /// A float literal, with a decimal point as C-like languages need
+    fn literal(&self, value: f32) -> String {
+        let mut literal = format!("{:.*}", self.precision, value);
+        if literal.starts_with('-') && literal[1..].chars().all(|c| c == '0' || c == '.') {
+            literal.remove(0);
+        }
+        if !literal.contains('.') {
+            literal.push_str(".0");
+        }
+        literal
+    }
+
+    fn source(&self, terms: &[(f32, f32, f32)]) -> String {
+        let rows: Vec<String> = terms
+            .iter()
+            .map(|&(frequency, radius, phase)| {
+                let values = format!(
+                    "{}, {}, {}",
+                    self.literal(frequency),
+                    self.literal(radius),
+                    self.literal(phase)
+                );
+                match self.language {
+                    CodeLanguage::Rust | CodeLanguage::Python => format!("({}),", values),
+                    CodeLanguage::JavaScript => format!("[{}],", values),
+                    CodeLanguage::Glsl => format!("vec3({}),", values),
+                }
+            })
+            .collect();
+        let count = terms.len();
+        let header = format!(
+            "Generated by fourier-svg: {} terms, y pointing {}",
+            count,
+            if self.y_up { "up" } else { "down" }
+        );
+
+        match self.language {
+            CodeLanguage::Rust => format!(
+                "// {header}\n\n\
+                 /// Terms of the series: frequency, radius, phase in radians\n\
+                 pub const FOURIER_TERMS: [(f32, f32, f32); {count}] = [\n    {rows}\n];\n\n\
+                 /// Point of the curve at `t`, one period running from 0 to 1\n\
+                 pub fn eval(t: f32) -> (f32, f32) {{\n    \
+                     let mut point = (0.0, 0.0);\n    \
+                     for &(frequency, radius, phase) in FOURIER_TERMS.iter() {{\n        \
+                         let angle = phase + core::f32::consts::TAU * frequency * t;\n        \
+                         point.0 += radius * angle.cos();\n        \
+                         point.1 += radius * angle.sin();\n    \
+                     }}\n    \
+                     point\n\
+                 }}\n",
+                rows = rows.join("\n    ")
+            ),
+            CodeLanguage::JavaScript => format!(
+                "// {header}\n\n\
+                 /** Terms of the series: frequency, radius, phase in radians */\n\
+                 export const FOURIER_TERMS = [\n  {rows}\n];\n\n\
+                 /** Point [x, y] of the curve at t, one period running from 0 to 1 */\n\
+                 export function evaluate(t) {{\n  \
+                     let x = 0;\n  \
+                     let y = 0;\n  \
+                     for (const [frequency, radius, phase] of FOURIER_TERMS) {{\n    \
+                         const angle = phase + 2 * Math.PI * frequency * t;\n    \
+                         x += radius * Math.cos(angle);\n    \
+                         y += radius * Math.sin(angle);\n  \
+                     }}\n  \
+                     return [x, y];\n\
+                 }}\n",
+                rows = rows.join("\n  ")
+            ),
+            CodeLanguage::Python => format!(
+                "# {header}\n\n\
+                 import math\n\n\
+                 # Terms of the series: frequency, radius, phase in radians\n\
+                 FOURIER_TERMS = (\n    {rows}\n)\n\n\n\
+                 def eval(t):\n    \
+                     \"\"\"Point (x, y) of the curve at t, one period running from 0 to 1\"\"\"\n    \
+                     x = y = 0.0\n    \
+                     for frequency, radius, phase in FOURIER_TERMS:\n        \
+                         angle = phase + math.tau * frequency * t\n        \
+                         x += radius * math.cos(angle)\n        \
+                         y += radius * math.sin(angle)\n    \
+                     return x, y\n",
+                rows = rows.join("\n    ")
+            ),
+            CodeLanguage::Glsl => {
+                // No trailing comma in a constructor
+                let rows = rows.join("\n    ");
+                format!(
+                    "// {header}\n\n\
+                     const int FOURIER_TERM_COUNT = {count};\n\n\
+                     // Terms of the series: frequency, radius, phase in radians\n\
+                     const vec3 FOURIER_TERMS[{count}] = vec3[{count}](\n    {rows}\n);\n\n\
+                     // Point of the curve at t, one period running from 0 to 1\n\
+                     vec2 fourier_eval(float t) {{\n    \
+                         vec2 point = vec2(0.0);\n    \
+                         for (int i = 0; i < FOURIER_TERM_COUNT; i++) {{\n        \
+                             vec3 term = FOURIER_TERMS[i];\n        \
+                             float angle = term.z + 6.28318530718 * term.x * t;\n        \
+                             point += term.y * vec2(cos(angle), sin(angle));\n    \
+                         }}\n    \
+                         return point;\n\
+                     }}\n",
+                    rows = rows.strip_suffix(',').unwrap_or(&rows)
+                )
+            }
+        }
+    }
+}
+
+/// `(frequency, radius, phase)` of the first `terms` rotating terms of
+/// `data`, after a single DC term. Conjugated for y pointing up.
+pub(crate) fn series_terms(
+    data: &[DrawData],
+    terms: Option<usize>,
+    y_up: bool,
+) -> Vec<(f32, f32, f32)> {
+    let mut epicycles = Epicycles::new(data);
+    if let Some(terms) = terms {
+        epicycles = epicycles.truncated(terms);
+    }
+    let (x, y) = epicycles.center();
+    let mut series = vec![(0.0, x.hypot(y), y.atan2(x))];
+    series.extend(
+        epicycles
+            .arms()
+            .iter()
+            .map(|arm| (arm.frequency, arm.radius, arm.angle)),
+    );
+    if y_up {
+        // conj(r e^(i (a + 2 PI f t))) = r e^(i (-a + 2 PI (-f) t))
+        for term in &mut series {
+            *term = (-term.0, term.1, -term.2);
+        }
+    }
+    series
+}
+
+impl Default for CodeVisualizer {
+    fn default() -> Self {
+        Self::new("output.rs".to_string())
+    }
+}
+
+impl Visualizer for CodeVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        if data
+            .iter()
+            .any(|d| !(d.frequency.is_finite() && d.radius.is_finite() && d.angle.is_finite()))
+        {
+            return Err(VisualizerError::InvalidData(
+                "cannot write non-finite coefficients as code".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(
+            self.source(&series_terms(&data, self.terms, self.y_up))
+                .as_bytes(),
+        )?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(visualizer: CodeVisualizer, data: &[DrawData]) -> String {
+        let mut buffer = Vec::new();
+        visualizer.render_to(data.to_vec(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_terms_in_every_language() {
+        let data = vec![
+            DrawData::new(0.0, 2.0, 0.0),
+            DrawData::new(1.0, 10.0, 0.5),
+            DrawData::new(-1.0, 3.0, 0.0),
+            DrawData::new(2.0, 1.0, 0.0),
+        ];
+        let visualizer = || CodeVisualizer::default().with_terms(2).with_precision(2);
+
+        let rust = render(visualizer(), &data);
+        assert!(rust.contains("[(f32, f32, f32); 3]"));
+        assert!(rust.contains("(0.00, 2.00, 0.00),\n    (1.00, 10.00, 0.50),"));
+        assert!(rust.contains("pub fn eval(t: f32) -> (f32, f32)"));
+        assert!(!rust.contains("(2.00,"));
+
+        // Mirrored for y pointing up
+        let up = render(visualizer().with_y_up(true), &data);
+        assert!(up.contains("(-1.00, 10.00, -0.50),\n    (1.00, 3.00, 0.00),"));
+
+        let js = render(visualizer().with_language(CodeLanguage::JavaScript), &data);
+        assert!(js.contains("export function evaluate(t)") && js.contains("[1.00, 10.00, 0.50],"));
+        let python = render(visualizer().with_language(CodeLanguage::Python), &data);
+        assert!(python.contains("def eval(t):") && python.contains("FOURIER_TERMS = (\n"));
+
+        let glsl = render(visualizer().with_language(CodeLanguage::Glsl), &data);
+        assert!(glsl.contains("const vec3 FOURIER_TERMS[3] = vec3[3]("));
+        assert!(glsl.contains("vec3(-1.00, 3.00, 0.00)\n);"));
+    }
+}
@@ -1,8 +1,10 @@
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::Write;
 
 use crate::fft_drawer::DrawData;
-use crate::visualizer::Visualizer;
+use crate::processor::PathLayer;
+use crate::theme::Theme;
+use crate::visualizer::{CountingWriter, RenderSummary, Visualizer, VisualizerError};
 use serde::{Deserialize, Serialize};
 
 /// Fourier data export format
@@ -14,6 +16,28 @@ pub struct FourierExport {
     pub metadata: ExportMetadata,
     /// The Fourier coefficient data
     pub data: Vec<FourierCoefficient>,
+    /// Paths drawn separately, each with its own coefficients
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<ExportLayer>,
+    /// Colors and line widths to draw the data with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    /// Points of the path as sampled before the transform, `[x, y]` each
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<Vec<(f32, f32)>>,
+}
+
+impl FourierExport {
+    pub fn new(data: &[DrawData], metadata: ExportMetadata) -> Self {
+        FourierExport {
+            version: "1.0".to_string(),
+            metadata,
+            data: data.iter().map(FourierCoefficient::from).collect(),
+            layers: Vec::new(),
+            theme: None,
+            samples: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +52,48 @@ pub struct ExportMetadata {
     pub timestamp: i64,
 }
 
+impl ExportMetadata {
+    /// Metadata of an export made now
+    pub fn new(svg_path: Option<String>, sample_count: usize, wave_count: usize) -> Self {
+        ExportMetadata {
+            svg_path,
+            sample_count,
+            wave_count,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        }
+    }
+}
+
+/// One path of a multi-path export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportLayer {
+    pub id: String,
+    /// Original SVG path string (if available)
+    pub svg_path: Option<String>,
+    pub visible: bool,
+    pub opacity: f32,
+    pub data: Vec<FourierCoefficient>,
+}
+
+impl From<&PathLayer> for ExportLayer {
+    fn from(layer: &PathLayer) -> Self {
+        ExportLayer {
+            id: layer.id.clone(),
+            svg_path: Some(layer.path_data.clone()),
+            visible: layer.visible,
+            opacity: layer.opacity,
+            data: layer
+                .fourier_data
+                .iter()
+                .map(FourierCoefficient::from)
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FourierCoefficient {
     /// Frequency index
@@ -38,11 +104,22 @@ pub struct FourierCoefficient {
     pub angle: f32,
 }
 
+impl From<&DrawData> for FourierCoefficient {
+    fn from(d: &DrawData) -> Self {
+        FourierCoefficient {
+            frequency: d.frequency,
+            radius: d.radius,
+            angle: d.angle,
+        }
+    }
+}
+
 pub struct ExportVisualizer {
     file_name: String,
     svg_path: Option<String>,
     sample_count: usize,
     wave_count: usize,
+    samples: Option<Vec<(f32, f32)>>,
 }
 
 impl ExportVisualizer {
@@ -52,6 +129,7 @@ impl ExportVisualizer {
             svg_path: None,
             sample_count: 0,
             wave_count: 0,
+            samples: None,
         }
     }
 
@@ -66,54 +144,49 @@ impl ExportVisualizer {
         self.wave_count = wave_count;
         self
     }
+
+    /// Include the points the path was sampled at
+    pub fn with_samples(mut self, samples: Vec<(f32, f32)>) -> Self {
+        self.samples = Some(samples);
+        self
+    }
+}
+
+impl Default for ExportVisualizer {
+    fn default() -> Self {
+        Self::new("output.json".to_string())
+    }
 }
 
 impl Visualizer for ExportVisualizer {
-    fn render(&self, data: Vec<DrawData>) -> bool {
-        // Convert DrawData to FourierCoefficient
-        let coefficients: Vec<FourierCoefficient> = data
-            .iter()
-            .map(|d| FourierCoefficient {
-                frequency: d.frequency,
-                radius: d.radius,
-                angle: d.angle,
-            })
-            .collect();
-
-        let export = FourierExport {
-            version: "1.0".to_string(),
-            metadata: ExportMetadata {
-                svg_path: self.svg_path.clone(),
-                sample_count: self.sample_count,
-                wave_count: self.wave_count,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as i64,
-            },
-            data: coefficients,
-        };
-
-        // Write to JSON file
-        match File::create(&self.file_name) {
-            Ok(file) => {
-                let writer = BufWriter::new(file);
-                match serde_json::to_writer_pretty(writer, &export) {
-                    Ok(_) => {
-                        println!("Fourier data exported to {}", self.file_name);
-                        true
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to write JSON: {}", e);
-                        false
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to create file: {}", e);
-                false
-            }
-        }
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// An empty export is still a valid file
+    fn check(&self, _data: &[DrawData]) -> Result<(), VisualizerError> {
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        let mut export = FourierExport::new(
+            &data,
+            ExportMetadata::new(self.svg_path.clone(), self.sample_count, self.wave_count),
+        );
+        export.samples = self.samples.clone();
+
+        let mut writer = CountingWriter::new(writer);
+        serde_json::to_writer_pretty(&mut writer, &export)?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
     }
 }
 
@@ -140,3 +213,43 @@ pub fn export_to_draw_data(export: &FourierExport) -> Vec<DrawData> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::test_data;
+
+    #[test]
+    fn test_samples_round_trip() {
+        let path = std::env::temp_dir().join("fourier_svg_samples_export.json");
+        let path = path.to_str().unwrap();
+        let samples = vec![(1.0, 2.5), (-3.0, 4.0)];
+        let visualizer = ExportVisualizer::new(path.to_string()).with_samples(samples.clone());
+        visualizer.render(test_data()).unwrap();
+
+        let export = load_fourier_export(path).unwrap();
+        assert_eq!(export.samples, Some(samples));
+        assert_eq!(export_to_draw_data(&export).len(), 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_export_without_newer_fields_loads() {
+        let path = std::env::temp_dir().join("fourier_svg_legacy_export.json");
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            r#"{
+                "version": "1.0",
+                "metadata": {"svg_path": null, "sample_count": 16, "wave_count": 1, "timestamp": 0},
+                "data": [{"frequency": 1.0, "radius": 2.0, "angle": 0.5}]
+            }"#,
+        )
+        .unwrap();
+
+        let export = load_fourier_export(path).unwrap();
+        assert!(export.samples.is_none() && export.theme.is_none() && export.layers.is_empty());
+        assert_eq!(export_to_draw_data(&export)[0].radius, 2.0);
+        std::fs::remove_file(path).unwrap();
+    }
+}
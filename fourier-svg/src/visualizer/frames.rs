@@ -0,0 +1,123 @@
+//! Frame scheduling shared by the animated raster visualizers
+//!
+//! Every frame is a pure function of its index, so with the `parallel`
+//! feature frames are rasterised on the rayon thread pool while the encoder
+//! works through the ones already rendered, in order. A bounded window of
+//! frames is in flight at a time. Without the feature they are rendered one
+//! by one.
+
+use crate::visualizer::VisualizerError;
+
+/// Render `count` frames with `render` and pass them to `encode` in order.
+///
+/// The encoder runs on the calling thread and waits for the frames, so this
+/// must not be called from a worker of the rayon pool.
+#[cfg(feature = "parallel")]
+pub(crate) fn render_in_order<T, R, E>(
+    count: usize,
+    render: R,
+    mut encode: E,
+) -> Result<(), VisualizerError>
+where
+    T: Send,
+    R: Fn(usize) -> T + Sync,
+    E: FnMut(T) -> Result<(), VisualizerError>,
+{
+    use std::collections::HashMap;
+    use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+    use std::sync::mpsc::sync_channel;
+
+    // Enough frames to keep every worker busy, few enough to bound memory
+    let window = (rayon::current_num_threads().max(1) * 2).min(count);
+    // Never more messages than frames in flight, so sending never blocks
+    let (sender, receiver) = sync_channel(window.max(1));
+    let render = &render;
+
+    rayon::in_place_scope(|scope| {
+        let spawn = |frame: usize| {
+            let sender = sender.clone();
+            scope.spawn(move |_| {
+                // A panic is sent too, the encoder would wait forever otherwise
+                let rendered = catch_unwind(AssertUnwindSafe(|| render(frame)));
+                // After an encoding error nobody receives, which is fine
+                let _ = sender.send((frame, rendered));
+            });
+        };
+
+        for frame in 0..window {
+            spawn(frame);
+        }
+        // Frames rendered ahead of the one being encoded
+        let mut ahead = HashMap::new();
+        for frame in 0..count {
+            let rendered = loop {
+                if let Some(rendered) = ahead.remove(&frame) {
+                    break rendered;
+                }
+                let (index, rendered) = receiver
+                    .recv()
+                    .expect("the sender outlives the frames in flight");
+                ahead.insert(index, rendered);
+            };
+            match rendered {
+                Ok(rendered) => encode(rendered)?,
+                Err(panic) => resume_unwind(panic),
+            }
+            if frame + window < count {
+                spawn(frame + window);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Render `count` frames with `render` and pass them to `encode` in order
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn render_in_order<T, R, E>(
+    count: usize,
+    render: R,
+    mut encode: E,
+) -> Result<(), VisualizerError>
+where
+    T: Send,
+    R: Fn(usize) -> T + Sync,
+    E: FnMut(T) -> Result<(), VisualizerError>,
+{
+    for frame in 0..count {
+        encode(render(frame))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_frames_are_encoded_in_order() {
+        // Early frames take longest, so they finish after later ones
+        let render = |frame: usize| {
+            std::thread::sleep(Duration::from_millis(((40 - frame) % 7) as u64));
+            frame
+        };
+        let mut encoded = Vec::new();
+        render_in_order(40, render, |frame| {
+            encoded.push(frame);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(encoded, (0..40).collect::<Vec<_>>());
+
+        let mut encoded = 0;
+        let result = render_in_order(40, render, |frame| match frame {
+            5 => Err(VisualizerError::InvalidData("stop".to_string())),
+            _ => {
+                encoded += 1;
+                Ok(())
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(encoded, 5);
+    }
+}
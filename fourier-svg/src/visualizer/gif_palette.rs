@@ -0,0 +1,192 @@
+//! Fixed GIF palette derived from a theme
+//!
+//! Frames only contain the theme colors and their anti-aliased blends with
+//! the background, so a palette built from those ahead of time replaces the
+//! per-frame NeuQuant quantisation. Index 0 is kept transparent, for a
+//! transparent background or for pixels unchanged since the previous frame.
+
+use std::collections::HashMap;
+
+use crate::theme::{Color, Theme};
+
+/// Index of the transparent color
+pub(crate) const TRANSPARENT_INDEX: u8 = 0;
+
+/// Most blend levels between a theme color and the background
+const MAX_BLEND_LEVELS: usize = 16;
+
+/// Colors sampled along a trail gradient
+const GRADIENT_SAMPLES: usize = 8;
+
+pub(crate) struct FixedPalette {
+    colors: Vec<[u8; 3]>,
+    transparent_background: bool,
+    cache: HashMap<[u8; 4], u8>,
+}
+
+impl FixedPalette {
+    pub(crate) fn from_theme(theme: &Theme) -> FixedPalette {
+        let background = theme.background;
+        let transparent_background = background.is_transparent();
+
+        let mut foreground = vec![theme.arm, theme.circle, theme.trail];
+        foreground.extend(theme.palette.iter().copied());
+        if !theme.trail_gradient.is_empty() {
+            foreground.extend(
+                (0..GRADIENT_SAMPLES)
+                    .map(|i| theme.trail_color(i as f32 / (GRADIENT_SAMPLES - 1) as f32)),
+            );
+        }
+        let mut opaque: Vec<Color> = Vec::new();
+        for color in foreground {
+            let color = Color { a: 255, ..color };
+            if !opaque.contains(&color) {
+                opaque.push(color);
+            }
+        }
+
+        let mut colors = vec![[0, 0, 0]];
+        if !transparent_background {
+            colors.push([background.r, background.g, background.b]);
+        }
+        // Blends over a transparent background keep their color, only their
+        // alpha changes, so one level is enough
+        let levels = if transparent_background {
+            1
+        } else {
+            ((256 - colors.len()) / opaque.len().max(1)).clamp(1, MAX_BLEND_LEVELS)
+        };
+        for color in &opaque {
+            for level in 1..=levels {
+                if colors.len() == 256 {
+                    break;
+                }
+                let blended = background.lerp(color, level as f32 / levels as f32);
+                let rgb = [blended.r, blended.g, blended.b];
+                if !colors[1..].contains(&rgb) {
+                    colors.push(rgb);
+                }
+            }
+        }
+
+        FixedPalette {
+            colors,
+            transparent_background,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Palette as the flat RGB triplets expected by the GIF encoder
+    pub(crate) fn rgb(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+
+    /// Index of the palette color closest to an RGBA pixel
+    pub(crate) fn index(&mut self, pixel: [u8; 4]) -> u8 {
+        if self.transparent_background && pixel[3] < 128 {
+            return TRANSPARENT_INDEX;
+        }
+        if let Some(index) = self.cache.get(&pixel) {
+            return *index;
+        }
+        let distance = |c: &[u8; 3]| {
+            (0..3)
+                .map(|i| (c[i] as i32 - pixel[i] as i32).pow(2))
+                .sum::<i32>()
+        };
+        let index = (1..self.colors.len())
+            .min_by_key(|i| distance(&self.colors[*i]))
+            .unwrap_or(0) as u8;
+        self.cache.insert(pixel, index);
+        index
+    }
+
+    /// Indexed pixels of an RGBA frame
+    pub(crate) fn indexed(&mut self, rgba: &[u8]) -> Vec<u8> {
+        rgba.chunks_exact(4)
+            .map(|p| self.index([p[0], p[1], p[2], p[3]]))
+            .collect()
+    }
+}
+
+/// Region of a frame that changed since the previous one
+pub(crate) struct FrameDiff {
+    pub left: u16,
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Indexed pixels of the region, unchanged ones transparent
+    pub pixels: Vec<u8>,
+}
+
+/// Crop `current` to the pixels differing from `previous`
+pub(crate) fn diff_frames(previous: &[u8], current: &[u8], width: u16, height: u16) -> FrameDiff {
+    let (w, h) = (width as usize, height as usize);
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (w, h, 0, 0);
+    for y in 0..h {
+        for x in 0..w {
+            if previous[y * w + x] != current[y * w + x] {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    // Nothing changed: a single transparent pixel keeps the frame timing
+    if min_x > max_x {
+        return FrameDiff {
+            left: 0,
+            top: 0,
+            width: 1,
+            height: 1,
+            pixels: vec![TRANSPARENT_INDEX],
+        };
+    }
+
+    let mut pixels = Vec::with_capacity((max_x - min_x + 1) * (max_y - min_y + 1));
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let i = y * w + x;
+            pixels.push(if previous[i] == current[i] {
+                TRANSPARENT_INDEX
+            } else {
+                current[i]
+            });
+        }
+    }
+    FrameDiff {
+        left: min_x as u16,
+        top: min_y as u16,
+        width: (max_x - min_x + 1) as u16,
+        height: (max_y - min_y + 1) as u16,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_and_diff() {
+        let mut palette = FixedPalette::from_theme(&Theme::default());
+        assert!(palette.rgb().len() <= 256 * 3);
+        let white = palette.index([255, 255, 255, 255]);
+        let arm = palette.index([202, 126, 86, 255]);
+        assert_ne!(white, TRANSPARENT_INDEX);
+        assert_ne!(white, arm);
+
+        let previous = vec![white; 12];
+        let mut current = previous.clone();
+        current[5] = arm;
+        current[10] = arm;
+        let diff = diff_frames(&previous, &current, 4, 3);
+        assert_eq!((diff.left, diff.top, diff.width, diff.height), (1, 1, 2, 2));
+        assert_eq!(
+            diff.pixels,
+            vec![arm, TRANSPARENT_INDEX, TRANSPARENT_INDEX, arm]
+        );
+    }
+}
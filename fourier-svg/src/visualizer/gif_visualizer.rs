@@ -1,198 +1,248 @@
-use std::fs::File;
-use std::io::BufWriter;
+use std::borrow::Cow;
+use std::io::Write;
 
 use crate::fft_drawer::DrawData;
-use crate::visualizer::Visualizer;
-use gif::{Encoder, Frame, Repeat};
-use image::{Rgb, RgbImage};
+use crate::framing::Framing;
+use crate::morph::MorphTarget;
+use crate::raster::RasterStyle;
+use crate::theme::Theme;
+use crate::visualizer::animation::Animation;
+use crate::visualizer::frames::render_in_order;
+use crate::visualizer::gif_palette::{diff_frames, FixedPalette, TRANSPARENT_INDEX};
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+/// Output of the per-frame work, before it is handed to the encoder in order
+enum RenderedFrame {
+    /// Pixels still to be mapped to the fixed palette
+    Rgba(Vec<u8>),
+    Quantized(Frame<'static>),
+}
+
+/// How RGBA frames are turned into GIF palette indices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GifQuantization {
+    /// One palette derived from the theme, shared by every frame
+    FixedPalette,
+    /// NeuQuant quantisation of every frame, `speed` from 1 (best) to 30
+    NeuQuant { speed: i32 },
+}
 
 pub struct GIFVisualizer {
     file_name: String,
-    width: u16,
-    height: u16,
-    frames: usize,
-    delay: u16,
+    animation: Animation,
+    quantization: GifQuantization,
+    frame_diff: bool,
 }
 
 impl GIFVisualizer {
     pub fn new(file_name: String) -> GIFVisualizer {
         GIFVisualizer {
             file_name,
-            width: 800,
-            height: 600,
-            frames: 100,
-            delay: 2, // 20ms per frame
+            animation: Animation::default(),
+            quantization: GifQuantization::FixedPalette,
+            frame_diff: true,
         }
     }
 
+    /// Frames to draw, the delay rounded to hundredths of a second
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+
     pub fn with_dimensions(mut self, width: u16, height: u16) -> Self {
-        self.width = width;
-        self.height = height;
+        self.animation = self.animation.with_dimensions(width as u32, height as u32);
         self
     }
 
+    /// Number of animated frames, spread evenly over every cycle
     pub fn with_frames(mut self, frames: usize) -> Self {
-        self.frames = frames;
+        self.animation = self.animation.with_frames(frames);
         self
     }
 
-    pub fn with_delay(mut self, delay: u16) -> Self {
-        self.delay = delay;
+    /// Number of full periods drawn by the animated frames
+    pub fn with_cycles(mut self, cycles: usize) -> Self {
+        self.animation = self.animation.with_cycles(cycles);
         self
     }
 
-    fn calculate_position(&self, data: &[DrawData], time: f32, idx: usize) -> (f32, f32) {
-        let mut x = self.width as f32 / 4.0;
-        let mut y = self.height as f32 / 2.0;
-
-        for (i, d) in data.iter().enumerate() {
-            let angle = d.angle + 2.0 * std::f32::consts::PI * time * (d.frequency / 20.0);
-            let radius = d.radius / 2.0;
-
-            x += radius * angle.cos();
-            y += radius * angle.sin();
-
-            // Only draw up to current index in partial trace
-            if i >= idx {
-                break;
-            }
-        }
-
-        (x, y)
+    /// Frames showing the finished drawing after the last cycle, see
+    /// `Animation::with_hold_frames`
+    pub fn with_hold_frames(mut self, hold_frames: usize) -> Self {
+        self.animation = self.animation.with_hold_frames(hold_frames);
+        self
     }
 
-    fn render_frame(&self, data: &[DrawData], time: f32, wave: &[(f32, f32)]) -> Vec<u8> {
-        let mut img = RgbImage::new(self.width as u32, self.height as u32);
-        let white = Rgb([255, 255, 255]);
-        let black = Rgb([0, 0, 0]);
-        let orange = Rgb([202, 126, 86]);
-
-        // Fill background
-        for pixel in img.pixels_mut() {
-            *pixel = white;
-        }
+    /// Length of the trail behind the pen, as a fraction of a period
+    pub fn with_trail(mut self, trail: f32) -> Self {
+        self.animation = self.animation.with_trail(trail);
+        self
+    }
 
-        // Draw wave trace
-        for i in 1..wave.len() {
-            let x0 = wave[i - 1].0 as i32;
-            let y0 = wave[i - 1].1 as i32;
-            let x1 = wave[i].0 as i32;
-            let y1 = wave[i].1 as i32;
+    /// Time each frame is shown, in hundredths of a second
+    pub fn with_delay(mut self, delay: u16) -> Self {
+        self.animation = self.animation.with_delay_ms(delay as u32 * 10);
+        self
+    }
 
-            self.draw_line(&mut img, x0, y0, x1, y1, black);
-        }
+    /// Morph the rendered shape into `target` while the animation plays.
+    /// The GIF is lengthened if needed so the morph can complete.
+    pub fn with_morph(mut self, target: MorphTarget) -> Self {
+        self.animation = self.animation.with_morph(target);
+        self
+    }
 
-        // Draw circles
-        let mut x = self.width as f32 / 4.0;
-        let mut y = self.height as f32 / 2.0;
-
-        for d in data {
-            let angle = d.angle + 2.0 * std::f32::consts::PI * time * (d.frequency / 20.0);
-            let radius = d.radius / 2.0;
-
-            let new_x = x + radius * angle.cos();
-            let new_y = y + radius * angle.sin();
-
-            // Draw line from center to edge
-            self.draw_line(
-                &mut img,
-                x as i32,
-                y as i32,
-                new_x as i32,
-                new_y as i32,
-                orange,
-            );
-
-            x = new_x;
-            y = new_y;
-        }
+    /// Control how the drawing is scaled and placed in the frame
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.animation = self.animation.with_framing(framing);
+        self
+    }
 
-        img.into_raw()
+    /// Colors, line widths and supersampling of the frames
+    pub fn with_style(mut self, style: RasterStyle) -> Self {
+        self.animation = self.animation.with_style(style);
+        self
     }
 
-    fn draw_line(&self, img: &mut RgbImage, x0: i32, y0: i32, x1: i32, y1: i32, color: Rgb<u8>) {
-        let mut x0 = x0;
-        let mut y0 = y0;
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
+    /// Colors and line widths of the frames, a transparent background
+    /// producing a transparent GIF
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.animation = self.animation.with_theme(theme);
+        self
+    }
 
-        loop {
-            if x0 >= 0 && y0 >= 0 && x0 < self.width as i32 && y0 < self.height as i32 {
-                img.put_pixel(x0 as u32, y0 as u32, color);
-            }
+    pub fn with_quantization(mut self, quantization: GifQuantization) -> Self {
+        self.quantization = quantization;
+        self
+    }
 
-            if x0 == x1 && y0 == y1 {
-                break;
-            }
+    /// Only store the pixels that changed since the previous frame, the
+    /// others being left transparent. Applies to the fixed palette on an
+    /// opaque background.
+    pub fn with_frame_diff(mut self, frame_diff: bool) -> Self {
+        self.frame_diff = frame_diff;
+        self
+    }
+}
 
-            let e2 = 2 * err;
-            if e2 > -dy {
-                err -= dy;
-                x0 += sx;
-            }
-            if e2 < dx {
-                err += dx;
-                y0 += sy;
-            }
-        }
+impl Default for GIFVisualizer {
+    fn default() -> Self {
+        Self::new("output.gif".to_string())
     }
 }
 
 impl Visualizer for GIFVisualizer {
-    fn render(&self, data: Vec<DrawData>) -> bool {
-        let file = match File::create(&self.file_name) {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("Failed to create file: {}", e);
-                return false;
-            }
-        };
-
-        let writer = BufWriter::new(file);
-        let mut encoder = match Encoder::new(writer, self.width, self.height, &[]) {
-            Ok(e) => e,
-            Err(e) => {
-                eprintln!("Failed to create encoder: {}", e);
-                return false;
-            }
-        };
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
 
-        // Set repeat to infinite
-        if let Err(e) = encoder.set_repeat(Repeat::Infinite) {
-            eprintln!("Failed to set repeat: {}", e);
-            return false;
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()?;
+        if self.animation.width() > u16::MAX as u32 || self.animation.height() > u16::MAX as u32 {
+            return Err(VisualizerError::InvalidData(format!(
+                "GIF frames cannot be {}x{} pixels",
+                self.animation.width(),
+                self.animation.height()
+            )));
         }
+        Ok(())
+    }
 
-        let time_step = 1.0 / self.frames as f32;
-        let mut wave = Vec::new();
-
-        for frame in 0..self.frames {
-            let time = frame as f32 * time_step;
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let width = self.animation.width() as u16;
+        let height = self.animation.height() as u16;
+        // GIF delays are in hundredths of a second
+        let delay = ((self.animation.delay_ms() + 5) / 10).min(u16::MAX as u32) as u16;
+        let theme = self.animation.theme();
+        let transparent_background = theme.background.is_transparent();
+        let mut palette = FixedPalette::from_theme(theme);
+        let frame_diff = self.frame_diff && !transparent_background;
+        let mut previous: Option<Vec<u8>> = None;
+
+        let writer = CountingWriter::new(writer);
+        let global_palette = match self.quantization {
+            GifQuantization::FixedPalette => palette.rgb(),
+            GifQuantization::NeuQuant { .. } => Vec::new(),
+        };
+        let mut encoder = Encoder::new(writer, width, height, &global_palette)?;
 
-            // Calculate current position and add to wave
-            let pos = self.calculate_position(&data, time, data.len());
-            wave.push(pos);
+        // Set repeat to infinite
+        encoder.set_repeat(Repeat::Infinite)?;
 
-            // Limit wave length
-            if wave.len() > 400 {
-                wave.remove(0);
-            }
+        let animation = self.animation.prepare(&data);
+        let frames = animation.frame_count();
 
-            // Render frame
-            let frame_data = self.render_frame(&data, time, &wave);
+        let render = |frame: usize| {
+            let mut pixels = animation.render(frame).into_raw();
 
-            let gif_frame = Frame::from_rgb(self.width, self.height, &frame_data);
-            if let Err(e) = encoder.write_frame(&gif_frame) {
-                eprintln!("Failed to write frame {}: {}", frame, e);
-                return false;
+            // NeuQuant only depends on the frame, so it runs with the rendering
+            match self.quantization {
+                GifQuantization::FixedPalette => RenderedFrame::Rgba(pixels),
+                GifQuantization::NeuQuant { speed } => RenderedFrame::Quantized(
+                    Frame::from_rgba_speed(width, height, &mut pixels, speed.clamp(1, 30)),
+                ),
             }
-        }
+        };
 
-        // Encoder auto-flushes on drop
-        println!("GIF saved to {}", self.file_name);
-        true
+        render_in_order(frames, render, |rendered| {
+            let mut gif_frame = match rendered {
+                RenderedFrame::Rgba(pixels) => {
+                    let indexed = palette.indexed(&pixels);
+                    let diff = previous
+                        .as_ref()
+                        .filter(|_| frame_diff)
+                        .map(|previous| diff_frames(previous, &indexed, width, height));
+                    let gif_frame = match diff {
+                        Some(diff) => Frame {
+                            left: diff.left,
+                            top: diff.top,
+                            width: diff.width,
+                            height: diff.height,
+                            buffer: Cow::Owned(diff.pixels),
+                            transparent: Some(TRANSPARENT_INDEX),
+                            ..Frame::default()
+                        },
+                        None => Frame {
+                            width,
+                            height,
+                            buffer: Cow::Owned(indexed.clone()),
+                            transparent: Some(TRANSPARENT_INDEX),
+                            ..Frame::default()
+                        },
+                    };
+                    previous = Some(indexed);
+                    gif_frame
+                }
+                RenderedFrame::Quantized(gif_frame) => gif_frame,
+            };
+            gif_frame.delay = delay;
+            gif_frame.dispose = if transparent_background {
+                // Clear the previous frame instead of drawing over it
+                DisposalMethod::Background
+            } else {
+                DisposalMethod::Keep
+            };
+            encoder.write_frame(&gif_frame)?;
+            Ok(())
+        })?;
+
+        // Write the trailer before counting the bytes
+        let writer = encoder.into_inner()?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames,
+        })
     }
 }
@@ -0,0 +1,345 @@
+/* FROM FourierFromSVG project */
+let canvas = null;
+let context = null;
+let time = 0;
+const Point = class {
+    constructor(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+    equals(point) {
+        return this.x === point.x && this.y === point.y;
+    }
+    toString() {
+        return '(' + this.x + ', ' + this.y + ')';
+    }
+};
+
+/* Same evaluation as fourier_svg::epicycles::Epicycles: the DC term is the
+   fixed anchor, every other term is an arm rotating once per period per unit
+   of frequency, with time normalised to one period */
+const Epicycles = class {
+    constructor(terms)
+    {
+        this.setTerms(terms);
+    }
+    setTerms(terms)
+    {
+        this.center = new Point(0, 0);
+        this.arms = [];
+        /* Number of arms drawn, the first ones being kept like
+           Epicycles::truncated */
+        this.count = Infinity;
+        for (const term of terms) {
+            if (term.s === 0) {
+                this.center = new Point(
+                    this.center.x + term.r * Math.cos(term.a),
+                    this.center.y + term.r * Math.sin(term.a));
+            } else {
+                this.arms.push(term);
+            }
+        }
+    }
+    joints(t)
+    {
+        let p = this.center;
+        const joints = [p];
+        for (const arm of this.arms.slice(0, this.count)) {
+            const angle = arm.a + 2 * Math.PI * arm.s * t;
+            p = new Point(p.x + arm.r * Math.cos(angle), p.y + arm.r * Math.sin(angle));
+            joints.push(p);
+        }
+        return joints;
+    }
+};
+
+/* A chain of epicycles and the trail it leaves, one per layer of the export */
+const Drawing = class {
+    constructor(terms, opacity)
+    {
+        this.epicycles = new Epicycles(terms);
+        this.opacity = opacity;
+        /* Points of the trail, newest first, with the time they were drawn at */
+        this.wave = [];
+    }
+};
+
+/* Coefficients of the export format, as used by Epicycles */
+function to_terms(coefficients) {
+    return coefficients.map(c => ({ s: c.frequency, r: c.radius, a: c.angle }));
+}
+
+let drawings = [];
+let settings = null;
+let view = null;
+let animation_id = 0;
+let morph_from = null;
+let morph_to = null;
+let morph_duration = 0;
+let morph_start = null;
+let theme = null;
+let playing = true;
+let last_frame = null;
+/* Periods drawn since the start, not wrapped around */
+let elapsed = 0;
+/* Multiplier of settings.speed set by the player controls */
+let speed = 1;
+let arm_count = Infinity;
+let show = { circles: true, arms: true, trail: true };
+let scrubbing = false;
+
+/* Draw the visible layers of `exported`, or its data when it has none */
+function init_fourier(canvas_elm, exported) {
+    canvas = canvas_elm;
+    context = canvas.getContext('2d');
+    if(animation_id !== 0)
+        window.cancelAnimationFrame(animation_id);
+    const layers = exported.layers || [];
+    drawings = layers.length === 0
+        ? [new Drawing(to_terms(exported.data), 1)]
+        : layers.filter(layer => layer.visible)
+            .map(layer => new Drawing(to_terms(layer.data), layer.opacity));
+    animation_id = window.requestAnimationFrame(draw);
+}
+
+function apply_morph() {
+    if (morph_to === null)
+        return;
+    if (morph_start === null)
+        morph_start = performance.now();
+    let p = morph_duration > 0 ? (performance.now() - morph_start) / 1000 / morph_duration : 1;
+    p = Math.min(1, p);
+    p = p * p * (3 - 2 * p);
+    const epicycles = drawings[0].epicycles;
+    epicycles.setTerms(morph_from.map((from, i) => ({
+        s: from.s,
+        r: from.r + (morph_to[i].r - from.r) * p,
+        a: from.a + (morph_to[i].a - from.a) * p,
+    })));
+    epicycles.count = arm_count;
+}
+
+/* Sample the trails behind the pens again, after a jump in time or a change
+   of the arms */
+function rebuild_waves() {
+    const steps = Math.ceil(settings.trail * 256);
+    for (const drawing of drawings) {
+        drawing.wave = [];
+        for (let i = 0; i <= steps; i++) {
+            const t = elapsed - settings.trail * i / steps;
+            const joints = drawing.epicycles.joints(t - Math.floor(t));
+            drawing.wave.push({ p: to_screen(joints[joints.length - 1]), t: t });
+        }
+    }
+}
+
+function to_screen(p) {
+    return new Point(p.x * view.scale + view.x, p.y * view.scale + view.y);
+}
+
+function parse_color(color) {
+    if (color === 'transparent')
+        return [0, 0, 0, 0];
+    const channel = i => parseInt(color.substr(i, 2), 16);
+    return [channel(1), channel(3), channel(5), color.length > 7 ? channel(7) : 255];
+}
+
+function css_color(c, alpha) {
+    return 'rgba(' + c[0] + ', ' + c[1] + ', ' + c[2] + ', ' + (c[3] / 255 * alpha) + ')';
+}
+
+/* Same colors as fourier_svg::theme::Theme */
+function palette_color(frequency) {
+    if (theme.palette.length === 0)
+        return null;
+    const k = Math.max(Math.round(Math.abs(frequency)) - 1, 0);
+    return parse_color(theme.palette[k % theme.palette.length]);
+}
+
+function arm_color(frequency) {
+    return css_color(palette_color(frequency) || parse_color(theme.arm), 1);
+}
+
+function circle_color(frequency) {
+    const circle = parse_color(theme.circle);
+    const color = palette_color(frequency);
+    return css_color(color ? [color[0], color[1], color[2], circle[3]] : circle, 1);
+}
+
+function trail_color(position, alpha) {
+    const stops = theme.trail_gradient.map(parse_color);
+    if (stops.length === 0)
+        return css_color(parse_color(theme.trail), alpha);
+    if (stops.length === 1)
+        return css_color(stops[0], alpha);
+    const scaled = Math.min(Math.max(position, 0), 1) * (stops.length - 1);
+    const i = Math.min(Math.floor(scaled), stops.length - 2);
+    const t = scaled - i;
+    return css_color(stops[i].map((c, j) => Math.round(c + (stops[i + 1][j] - c) * t)), alpha);
+}
+
+function draw_circles(ctx, epicycles, joints) {
+    for (let i = 0; i + 1 < joints.length; i++) {
+        const center = to_screen(joints[i]);
+        const radius = Math.abs(epicycles.arms[i].r) * view.scale;
+        if (radius < 1)
+            continue;
+        ctx.beginPath();
+        ctx.arc(center.x, center.y, radius, 0, 2 * Math.PI);
+        ctx.strokeStyle = circle_color(epicycles.arms[i].s);
+        ctx.lineWidth = theme.circle_width;
+        ctx.stroke();
+    }
+}
+
+function draw_arms(ctx, epicycles, joints) {
+    joints = joints.map(to_screen);
+    for (let i = 1; i < joints.length; i++) {
+        ctx.beginPath();
+        ctx.moveTo(joints[i-1].x, joints[i-1].y);
+        ctx.lineTo(joints[i].x, joints[i].y);
+        ctx.strokeStyle = arm_color(epicycles.arms[i-1].s);
+        ctx.lineWidth = theme.arm_width;
+        ctx.stroke();
+    }
+}
+
+function draw_wave(ctx, wave) {
+    for (let i = 1; i < wave.length; i++) {
+        ctx.beginPath();
+        ctx.moveTo(wave[i-1].p.x, wave[i-1].p.y);
+        ctx.lineTo(wave[i].p.x, wave[i].p.y);
+        let position = 1 - (elapsed - wave[i].t) / settings.trail;
+        ctx.strokeStyle = trail_color(position, position);
+        ctx.lineWidth = theme.trail_width;
+        ctx.stroke();
+    }
+}
+
+function draw(now) {
+    // Advance by the time since the last frame, whatever the refresh rate
+    const seconds = last_frame === null ? 0 : Math.min((now - last_frame) / 1000, 0.1);
+    last_frame = now;
+    if (playing && !scrubbing) {
+        elapsed += seconds * settings.speed * speed;
+        time = elapsed - Math.floor(elapsed);
+        const scrubber = document.getElementById('fourier_time');
+        if (scrubber)
+            scrubber.value = time;
+    }
+
+    context.clearRect(0,0, canvas.width, canvas.height);
+    if (theme.background !== 'transparent') {
+        context.fillStyle = theme.background;
+        context.fillRect(0, 0, canvas.width, canvas.height);
+    }
+    apply_morph();
+    for (const drawing of drawings) {
+        const joints = drawing.epicycles.joints(time);
+        const wave = drawing.wave;
+        context.globalAlpha = drawing.opacity;
+        if (show.circles)
+            draw_circles(context, drawing.epicycles, joints);
+        if (show.arms)
+            draw_arms(context, drawing.epicycles, joints);
+
+        if (wave.length === 0 || wave[0].t !== elapsed)
+            wave.unshift({ p: to_screen(joints[joints.length - 1]), t: elapsed });
+        while (wave.length > 1 && elapsed - wave[wave.length - 1].t > settings.trail)
+            wave.pop();
+        if (show.trail)
+            draw_wave(context, wave);
+    }
+    context.globalAlpha = 1;
+
+    animation_id = window.requestAnimationFrame(draw);
+}
+
+/* The embedded export, in the JSON format of fourier_svg */
+function download_json() {
+    const json = JSON.stringify(fourier.data, null, 2);
+    const url = URL.createObjectURL(new Blob([json], { type: 'application/json' }));
+    const link = document.createElement('a');
+    link.href = url;
+    link.download = 'fourier.json';
+    document.body.appendChild(link);
+    link.click();
+    link.remove();
+    URL.revokeObjectURL(url);
+}
+
+function init_controls() {
+    const control = id => document.getElementById('fourier_' + id);
+    const play = control('play');
+    if (!play)
+        return;
+    play.addEventListener('click', () => {
+        playing = !playing;
+        play.textContent = playing ? 'Pause' : 'Play';
+    });
+
+    const speed_input = control('speed');
+    if (speed_input) {
+        speed_input.addEventListener('input', () => {
+            speed = parseFloat(speed_input.value);
+            control('speed_value').textContent = speed.toFixed(2) + '\u00d7';
+        });
+    }
+
+    const scrubber = control('time');
+    if (scrubber) {
+        scrubber.addEventListener('pointerdown', () => { scrubbing = true; });
+        scrubber.addEventListener('change', () => { scrubbing = false; });
+        scrubber.addEventListener('input', () => {
+            time = parseFloat(scrubber.value);
+            elapsed = Math.floor(elapsed) + time;
+            rebuild_waves();
+        });
+    }
+
+    const terms = control('terms');
+    if (terms) {
+        const arms = Math.max(...drawings.map(drawing => drawing.epicycles.arms.length));
+        terms.max = arms;
+        terms.value = arms;
+        control('terms_value').textContent = arms;
+        terms.addEventListener('input', () => {
+            arm_count = parseInt(terms.value);
+            for (const drawing of drawings)
+                drawing.epicycles.count = arm_count;
+            control('terms_value').textContent = arm_count;
+            rebuild_waves();
+        });
+    }
+
+    const info = control('info');
+    if (info) {
+        const metadata = fourier.data.metadata;
+        const layers = fourier.data.layers || [];
+        info.textContent = fourier.data.data.length + ' coefficients'
+            + (metadata.sample_count > 0 ? ' from ' + metadata.sample_count + ' samples' : '')
+            + (layers.length > 0 ? ', ' + layers.length + ' layers' : '');
+    }
+
+    for (const name of Object.keys(show)) {
+        const toggle = control('show_' + name);
+        if (toggle)
+            toggle.addEventListener('change', () => { show[name] = toggle.checked; });
+    }
+
+    const download = control('download');
+    if (download)
+        download.addEventListener('click', download_json);
+}
+
+window.onload = function() {
+    canvas = document.getElementById("fourier_canvas");
+    settings = fourier.settings;
+    morph_from = to_terms(fourier.data.data);
+    morph_to = fourier.morph_to && to_terms(fourier.morph_to);
+    morph_duration = settings.morph_duration;
+    view = settings.view;
+    theme = fourier.data.theme;
+    init_fourier(canvas, fourier.data);
+    init_controls();
+};
@@ -1,170 +1,480 @@
-use std::fs;
-use std::io::Error;
+//! HTML - Canvas animation of the epicycles in a web page
+//!
+//! The page is built from a template where `{{name}}` placeholders are
+//! replaced by the drawing and its settings:
+//!
+//! - `{{title}}`: page title, HTML-escaped
+//! - `{{width}}`, `{{height}}`: canvas size in pixels
+//! - `{{controls}}`: player controls when enabled, else nothing. They are found
+//!   by their `fourier_*` ids, so a template may also bring its own
+//! - `{{script}}`: the built-in player, drawing on `<canvas id="fourier_canvas">`
+//! - `{{data}}`: the drawing as a `FourierExport`, with its metadata, layers
+//!   and theme
+//! - `{{morph_to}}`, `{{settings}}`, `{{theme}}`: other JSON used by the
+//!   player, for templates bringing their own script
+//!
+//! Unknown placeholders are left as they are. The JSON values can be pasted in
+//! a script, even within a template literal: characters that could end the
+//! script or the literal are escaped, and coefficients that are not finite
+//! numbers are left out.
 
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::epicycles::Epicycles;
 use crate::fft_drawer;
-use crate::visualizer::Visualizer;
+use crate::framing::{morph_bounds, Bounds, Framing, BOUNDS_SAMPLES};
+use crate::morph::{Morph, MorphTarget};
+use crate::processor::PathLayer;
+use crate::theme::Theme;
+use crate::visualizer::export_visualizer::{
+    ExportLayer, ExportMetadata, FourierCoefficient, FourierExport,
+};
+use crate::visualizer::{check_data, RenderSummary, Visualizer, VisualizerError};
+
+/// Page used unless a custom template is given
+const TEMPLATE: &str = include_str!("html_template.html");
+
+/// Player drawing the epicycles, reading the `fourier` object set before it
+const PLAYER: &str = include_str!("html_player.js");
+
+/// Markup of the player controls, wired up by the player from their ids
+const CONTROLS: &str = r#"<div id="fourier_controls" style="font-family: sans-serif; display: flex; flex-wrap: wrap; gap: 0.5em 1.5em; align-items: center;">
+    <button id="fourier_play">Pause</button>
+    <label>Speed <input id="fourier_speed" type="range" min="0" max="4" step="0.05" value="1"> <span id="fourier_speed_value">1.00&times;</span></label>
+    <label>Time <input id="fourier_time" type="range" min="0" max="1" step="0.001" value="0"></label>
+    <label>Circles <input id="fourier_terms" type="range" min="1" max="1" step="1" value="1"> <span id="fourier_terms_value"></span></label>
+    <label><input id="fourier_show_circles" type="checkbox" checked> Circles</label>
+    <label><input id="fourier_show_arms" type="checkbox" checked> Arms</label>
+    <label><input id="fourier_show_trail" type="checkbox" checked> Trail</label>
+    <button id="fourier_download">Download JSON</button>
+    <span id="fourier_info"></span>
+</div>"#;
+
+/// Page and player settings of the HTML visualizer
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    width: u32,
+    height: u32,
+    speed: f32,
+    trail: f32,
+    theme: Theme,
+    title: String,
+    controls: bool,
+    template: Option<String>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            speed: 0.12,
+            trail: 0.8,
+            theme: Theme::default(),
+            title: "Fourier Visualizer".to_string(),
+            controls: false,
+            template: None,
+        }
+    }
+}
+
+impl HtmlOptions {
+    /// Size of the canvas in pixels
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Periods drawn per second
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Length of the trail behind the pen, as a fraction of a period
+    pub fn with_trail(mut self, trail: f32) -> Self {
+        self.trail = trail.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Colors and line widths of the animation
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Add an interactive player under the canvas: play/pause, speed, a time
+    /// scrubber, the number of circles drawn, toggles for the circles, arms
+    /// and trail, and a download of the coefficients as a JSON export
+    pub fn with_controls(mut self, controls: bool) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    /// Page markup with placeholders, see the module documentation
+    pub fn with_template(mut self, template: String) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Read the page template from a file
+    pub fn with_template_file(self, path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let template = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read template {}: {}", path.display(), e))?;
+        Ok(self.with_template(template))
+    }
+
+    fn check(&self) -> Result<(), VisualizerError> {
+        if self.width == 0 || self.height == 0 || !self.speed.is_finite() {
+            return Err(VisualizerError::InvalidData(format!(
+                "cannot play at {} periods per second on a {}x{} canvas",
+                self.speed, self.width, self.height
+            )));
+        }
+        if let Some(template) = &self.template {
+            if !template.contains("{{script}}") && !template.contains("{{data}}") {
+                return Err(VisualizerError::InvalidData(
+                    "the HTML template has neither a {{script}} nor a {{data}} placeholder"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
 
 pub struct HTMLVisualizer {
     file_name: String,
+    morph: Option<MorphTarget>,
+    framing: Framing,
+    options: HtmlOptions,
+    layers: Vec<PathLayer>,
+    svg_path: Option<String>,
+    sample_count: usize,
+    wave_count: usize,
 }
 
 impl HTMLVisualizer {
     pub fn new(file_name: String) -> HTMLVisualizer {
-        HTMLVisualizer { file_name }
+        HTMLVisualizer {
+            file_name,
+            morph: None,
+            framing: Framing::default(),
+            options: HtmlOptions::default(),
+            layers: Vec::new(),
+            svg_path: None,
+            sample_count: 0,
+            wave_count: 0,
+        }
+    }
+
+    /// Canvas, player and page settings
+    pub fn with_options(mut self, options: HtmlOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Control how the drawing is scaled and placed on the canvas
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Colors and line widths of the animation
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.options = self.options.with_theme(theme);
+        self
+    }
+
+    /// Morph the rendered shape into `target` once the page loads
+    pub fn with_morph(mut self, target: MorphTarget) -> Self {
+        self.morph = Some(target);
+        self
+    }
+
+    /// Draw every visible path with its own epicycles instead of the
+    /// rendered data, which is still embedded as the combined drawing
+    pub fn with_layers(mut self, layers: Vec<PathLayer>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Metadata embedded with the data, as in the JSON export
+    pub fn with_metadata(
+        mut self,
+        svg_path: Option<String>,
+        sample_count: usize,
+        wave_count: usize,
+    ) -> Self {
+        self.svg_path = svg_path;
+        self.sample_count = sample_count;
+        self.wave_count = wave_count;
+        self
+    }
+}
+
+/// Replace the `{{name}}` placeholders of `template`, in a single pass so
+/// that inserted values are never expanded themselves
+fn fill_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after
+            .find("}}")
+            .and_then(|end| Some((end, value(after[..end].trim())?)))
+        {
+            Some((end, replacement)) => {
+                filled.push_str(&replacement);
+                rest = &after[end + 2..];
+            }
+            None => {
+                filled.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Terms the page can draw, without NaN or infinite values
+fn finite(data: &[fft_drawer::DrawData]) -> Vec<fft_drawer::DrawData> {
+    data.iter()
+        .filter(|d| d.frequency.is_finite() && d.radius.is_finite() && d.angle.is_finite())
+        .cloned()
+        .collect()
+}
+
+fn coefficients(data: &[fft_drawer::DrawData]) -> Vec<FourierCoefficient> {
+    data.iter().map(FourierCoefficient::from).collect()
+}
+
+/// JSON of `value` that can be pasted in a script, even within a template
+/// literal. The escaped characters only appear within JSON strings, where the
+/// `\uXXXX` escapes keep their meaning.
+fn script_json<T: Serialize>(value: &T) -> Result<String, VisualizerError> {
+    let json = serde_json::to_string(value)?;
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' | '>' | '&' | '`' | '$' | '\u{2028}' | '\u{2029}' => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    Ok(escaped)
+}
+
+impl Default for HTMLVisualizer {
+    fn default() -> Self {
+        Self::new("output.html".to_string())
     }
 }
 
 impl Visualizer for HTMLVisualizer {
-    fn render(&self, data: Vec<fft_drawer::DrawData>) -> bool {
-        let fourier_json_data: String = data
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[fft_drawer::DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        if self.morph.is_some() && !self.layers.is_empty() {
+            return Err(VisualizerError::InvalidData(
+                "layers cannot be morphed".to_string(),
+            ));
+        }
+        self.options.check()
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<fft_drawer::DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let data = finite(&data);
+        let layers: Vec<PathLayer> = self
+            .layers
             .iter()
-            .map(|d| {
-                format!(
-                    "{{\"s\": {:?}, \"r\": {:?}, \"a\": {:?}}},",
-                    d.frequency, d.radius, d.angle
-                )
+            .map(|layer| PathLayer {
+                fourier_data: finite(&layer.fourier_data),
+                ..layer.clone()
             })
             .collect();
-        // Strip the last comma
-        let final_fourier_json_data: &str = if fourier_json_data.len() > 1 {
-            &fourier_json_data[0..fourier_json_data.len() - 1]
-        } else {
-            &fourier_json_data
+
+        // When morphing, both shapes are emitted aligned by frequency so the
+        // page can interpolate them entry by entry
+        let (data, morph_target, morph_duration, bounds) = match &self.morph {
+            Some(target) => {
+                let morph = Morph::new(&data, &finite(&target.data), &target.options);
+                (
+                    morph.from_data(),
+                    script_json(&coefficients(&morph.to_data()))?,
+                    target.duration,
+                    morph_bounds(&morph, BOUNDS_SAMPLES),
+                )
+            }
+            None if !layers.is_empty() => {
+                let mut bounds = Bounds::empty();
+                for layer in layers.iter().filter(|layer| layer.visible) {
+                    bounds.union(&Epicycles::new(&layer.fourier_data).bounds(BOUNDS_SAMPLES));
+                }
+                (data, "null".to_string(), 0.0, bounds)
+            }
+            None => {
+                let bounds = Epicycles::new(&data).bounds(BOUNDS_SAMPLES);
+                (data, "null".to_string(), 0.0, bounds)
+            }
         };
-        let content = format!(
-            "<html>
-<head>
-    <title>Fourier Visualizer</title>
-</head>
-<canvas id=\"fourier_canvas\" width=\"800\" height=\"600\"></canvas>
-<script>
-/* FROM FourierFromSVG project */
-let canvas = null; 
-let context = null;
-let time = 0;
-const Point = class {{
-    constructor(x, y) {{
-        this.x = x;
-        this.y = y;
-    }}
-    equals(point) {{
-        return this.x === point.x && this.y === point.y;
-    }}
-    toString() {{
-        return '(' + this.x + ', ' + this.y + ')';
-    }}
-}};
-
-const FourierCircle = class {{
-    constructor(speed, radius, initial_angle)
-    {{
-        this.radius = radius/2;
-        this.speed = speed/20;
-        this.initial_angle = initial_angle
-    }}
-    draw(ctx, at) 
-    {{
-        ctx.beginPath();
-        //ctx.arc(at.x, at.y, this.radius, 0, Math.PI * 2, true);
-        var x = at.x + this.radius * Math.cos(this.initial_angle + 2 * Math.PI * time * this.speed);
-        var y = at.y + this.radius * Math.sin(this.initial_angle + 2 * Math.PI * time * this.speed);
-        ctx.moveTo(at.x, at.y);
-        ctx.lineTo(x, y);
-        ctx.closePath();
-        ctx.strokeStyle = 'rgba(202, 126, 86, 0.7)';
-        ctx.lineWidth = 1; 
-        ctx.stroke();
-    }}
-
-    nextCenter(at) 
-    {{
-        var x = at.x + this.radius * Math.cos(this.initial_angle + 2 * Math.PI * time * this.speed);
-        var y = at.y + this.radius * Math.sin(this.initial_angle + 2 * Math.PI * time * this.speed);
-        return new Point(x, y)
-    }}
-}};
-
-let n;
-let circles;
-let animation_id = 0;
-let center = new Point(150, 150);
-let wave = [];
-
-function init_fourier(canvas_elm, constants, count) {{
-    canvas = canvas_elm;
-    context = canvas.getContext('2d');
-    if(animation_id !== 0)
-        window.cancelAnimationFrame(animation_id);
-    n = count;
-    circles = [];
-    wave = [];
-    for (let i = 0; i < count; i++) {{
-        let constant = constants[i];
-        circles[i] = new FourierCircle(constant.s, constant.r, constant.a);
-    }}
-    animation_id = window.requestAnimationFrame(draw);
-}}
-
-function draw_wave(ctx) {{
-    // ctx.beginPath();
-    for (let i = 1; i < wave.length; i++) {{
-        ctx.beginPath();
-        ctx.moveTo(wave[i-1].x, wave[i-1].y);
-        ctx.lineTo(wave[i].x, wave[i].y);
-        ctx.closePath();
-
-        // let c = Math.ceil(127.0 + 128.0*i/wave.length);
-        let alpha = 1 - i*1.0/wave.length;
-        
-        ctx.strokeStyle = 'rgba(0, 0, 0, ' + alpha + ')';
-        //ctx.strokeStyle = 'rgba(0, 0, 0, 1)';
-        ctx.lineWidth = 1;
-        ctx.stroke();
-    }}
-    // ctx.closePath();
-    // ctx.strokeStyle = 'rgba(0, 0, 0, 1)';
-    // ctx.stroke();
-}}
-
-function draw() {{
-    context.clearRect(0,0, canvas.width, canvas.height);
-    // let new_center = center;
-    let new_center = circles[0].nextCenter(center);
-    for(let i = 1; i < n; i++) {{
-        circles[i].draw(context, new_center);
-        new_center = circles[i].nextCenter(new_center);
-    }}
-    
-    wave.unshift(new_center);
-    draw_wave(context);
-
-    animation_id = window.requestAnimationFrame(draw);
-
-    time += 0.04;
-    if(wave.length > 400) {{
-        wave.pop();
-    }}
-}}
-/* GEN */
-window.onload = function() {{
-    canvas = document.getElementById(\"fourier_canvas\");
-    let data = JSON.parse(`[{}]`);
-    init_fourier(canvas, data, {:?});
-}};
-</script>
-</html>",
-            final_fourier_json_data,
-            data.len()
+
+        let options = &self.options;
+        let mut export = FourierExport::new(
+            &data,
+            ExportMetadata::new(self.svg_path.clone(), self.sample_count, self.wave_count),
         );
+        export.layers = layers.iter().map(ExportLayer::from).collect();
+        export.theme = Some(options.theme.clone());
 
-        let save_to_file = |file_name: &str| -> Result<(), Error> {
-            fs::write(file_name, content)?;
-            Ok(())
-        };
-        if let Err(_err) = save_to_file(&self.file_name) {
-            return false;
+        let transform =
+            self.framing
+                .transform(&bounds, options.width as f32, options.height as f32);
+        let settings = serde_json::json!({
+            "width": options.width,
+            "height": options.height,
+            "speed": options.speed,
+            "trail": options.trail,
+            "controls": options.controls,
+            "morph_duration": morph_duration,
+            "view": {
+                "scale": transform.scale,
+                "x": transform.offset.0,
+                "y": transform.offset.1,
+            },
+        });
+        let data = script_json(&export)?;
+        let theme = script_json(&options.theme)?;
+        let settings = script_json(&settings)?;
+        let script = format!(
+            "<script>\nconst fourier = {{\"data\": {}, \"morph_to\": {}, \"settings\": {}}};\n{}</script>",
+            data, morph_target, settings, PLAYER
+        );
+        let controls = if options.controls { CONTROLS } else { "" };
+
+        let template = options.template.as_deref().unwrap_or(TEMPLATE);
+        let content = fill_template(template, |name| match name {
+            "title" => Some(escape_html(&options.title)),
+            "width" => Some(options.width.to_string()),
+            "height" => Some(options.height.to_string()),
+            "controls" => Some(controls.to_string()),
+            "script" => Some(script.clone()),
+            "data" => Some(data.clone()),
+            "morph_to" => Some(morph_target.clone()),
+            "settings" => Some(settings.clone()),
+            "theme" => Some(theme.clone()),
+            _ => None,
+        });
+
+        writer.write_all(content.as_bytes())?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: content.len() as u64,
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::{render_test_data, test_data};
+    use crate::DrawData;
+
+    #[test]
+    fn test_custom_template() {
+        let options = HtmlOptions::default()
+            .with_dimensions(320, 200)
+            .with_title("Kiwi <3".to_string())
+            .with_template(
+                "<h1>{{title}}</h1>{{ width }}x{{height}} {{unknown}} {{data}}".to_string(),
+            );
+        let visualizer = HTMLVisualizer::default().with_options(options);
+
+        let (_, buffer) = render_test_data(&visualizer);
+        let page = String::from_utf8(buffer).unwrap();
+        assert!(page.starts_with("<h1>Kiwi &lt;3</h1>320x200 {{unknown}} {\"version\":\"1.0\""));
+
+        // The default page embeds the player, and its controls on request
+        let controls = HtmlOptions::default().with_controls(true);
+        let visualizer = HTMLVisualizer::default().with_options(controls);
+        let (_, buffer) = render_test_data(&visualizer);
+        let page = String::from_utf8(buffer).unwrap();
+        assert!(page.contains("<canvas id=\"fourier_canvas\" width=\"800\" height=\"600\">"));
+        assert!(page.contains("id=\"fourier_play\"") && page.contains("const fourier = "));
+        for control in ["speed", "time", "terms", "show_trail", "download"] {
+            assert!(page.contains(&format!("id=\"fourier_{}\"", control)));
         }
 
-        true
+        let no_data = HtmlOptions::default().with_template("<html></html>".to_string());
+        let visualizer = HTMLVisualizer::default().with_options(no_data);
+        assert!(visualizer.render_to(test_data(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_embedded_export_is_escaped() {
+        let data = vec![
+            DrawData::new(0.0, 10.0, 0.0),
+            DrawData::new(1.0, f32::NAN, 0.0),
+            DrawData::new(-1.0, 20.0, f32::INFINITY),
+            DrawData::new(2.0, 5.0, 1.0),
+        ];
+        let svg_path = "M 0 0 </script><script>alert(`${1}`)</script>";
+        let layer = PathLayer {
+            id: "kiwi".to_string(),
+            path_data: svg_path.to_string(),
+            fourier_data: data.clone(),
+            visible: true,
+            opacity: 0.5,
+        };
+        let visualizer = HTMLVisualizer::default()
+            .with_metadata(Some(svg_path.to_string()), 256, 4)
+            .with_layers(vec![layer]);
+
+        let mut buffer = Vec::new();
+        visualizer.render_to(data, &mut buffer).unwrap();
+        let page = String::from_utf8(buffer).unwrap();
+        assert_eq!(page.matches("</script>").count(), 1);
+
+        let start = page.find("const fourier = ").unwrap() + "const fourier = ".len();
+        let end = start + page[start..].find(";\n").unwrap();
+        assert!(!page[start..end].contains('`') && page[start..end].contains("\\u0060"));
+        let fourier: serde_json::Value = serde_json::from_str(&page[start..end]).unwrap();
+        let export: FourierExport = serde_json::from_value(fourier["data"].clone()).unwrap();
+        assert_eq!(export.metadata.svg_path.as_deref(), Some(svg_path));
+        assert_eq!(export.data.len(), 2);
+        assert_eq!(export.layers[0].data.len(), 2);
+        assert_eq!(export.layers[0].svg_path.as_deref(), Some(svg_path));
+        assert_eq!(export.theme, Some(Theme::default()));
     }
 }
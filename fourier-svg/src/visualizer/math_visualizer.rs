@@ -0,0 +1,312 @@
+//! Math - The series written as equations, for teaching
+//!
+//! LaTeX gives the parametric pair `x(t)`, `y(t)` in an `align*` environment
+//! (amsmath), or the complex sum `z(t)`. Desmos gets one expression per line,
+//! to paste into its expression list: the functions `f_x` and `f_y` and the
+//! parametric curve they draw over its default domain `0 <= t <= 1`.
+//!
+//! Coefficients are rounded to a number of decimals and the terms too small
+//! to matter are left out, so the equations stay readable.
+
+use std::io::Write;
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::code_visualizer::series_terms;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// How the series is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathFormat {
+    /// `x(t)` and `y(t)` as sums of cosines and sines
+    Latex,
+    /// `z(t)` as a sum of complex exponentials
+    LatexComplex,
+    /// Expressions to paste into Desmos
+    Desmos,
+}
+
+impl MathFormat {
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            MathFormat::Latex | MathFormat::LatexComplex => "tex",
+            MathFormat::Desmos => "txt",
+        }
+    }
+}
+
+pub struct MathVisualizer {
+    file_name: String,
+    format: MathFormat,
+    precision: usize,
+    tolerance: f32,
+    terms: Option<usize>,
+    y_up: bool,
+}
+
+impl MathVisualizer {
+    pub fn new(file_name: String) -> MathVisualizer {
+        MathVisualizer {
+            file_name,
+            format: MathFormat::Latex,
+            precision: 2,
+            tolerance: 0.001,
+            terms: None,
+            y_up: false,
+        }
+    }
+
+    pub fn with_format(mut self, format: MathFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Decimals the coefficients are rounded to
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Leave out the terms with a radius below this fraction of the largest
+    /// one. Terms rounding to 0 are always left out.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Only write the first `terms` rotating terms
+    pub fn with_terms(mut self, terms: usize) -> Self {
+        self.terms = Some(terms);
+        self
+    }
+
+    /// Flip the curve for y pointing up, instead of down as in SVG
+    pub fn with_y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// A rounded value without trailing zeros
+    fn number(&self, value: f32) -> String {
+        let rounded = format!("{:.*}", self.precision, value);
+        let rounded = match rounded.contains('.') {
+            true => rounded.trim_end_matches('0').trim_end_matches('.'),
+            false => &rounded,
+        };
+        match rounded {
+            "-0" => "0".to_string(),
+            _ => rounded.to_string(),
+        }
+    }
+
+    /// `2 PI f t`, the rotation of a term
+    fn rotation(&self, frequency: f32) -> String {
+        let factor = self.number(2.0 * frequency);
+        match factor.strip_prefix('-').unwrap_or(&factor) {
+            "1" => format!("{}\\pi t", factor.trim_end_matches('1')),
+            _ => format!("{}\\pi t", factor),
+        }
+    }
+
+    /// `value` added to a sum: ` + value`, ` - value`, or `value` first
+    fn signed(&self, sum: &mut String, value: &str) {
+        match (sum.is_empty(), value.strip_prefix('-')) {
+            (true, _) => sum.push_str(value),
+            (false, Some(magnitude)) => sum.push_str(&format!(" - {}", magnitude)),
+            (false, None) => sum.push_str(&format!(" + {}", value)),
+        }
+    }
+
+    /// `radius f(2 PI f t + phase)` summed over the terms, with `f` cos or sin
+    fn trigonometric_sum(
+        &self,
+        constant: f32,
+        terms: &[(f32, f32, f32)],
+        function: &str,
+    ) -> String {
+        let mut sum = String::new();
+        if self.number(constant) != "0" {
+            sum.push_str(&self.number(constant));
+        }
+        for &(frequency, radius, phase) in terms {
+            let mut angle = self.rotation(frequency);
+            if self.number(phase) != "0" {
+                self.signed(&mut angle, &self.number(phase));
+            }
+            let radius = match self.number(radius) {
+                r if r == "1" || r == "-1" => r.trim_end_matches('1').to_string(),
+                r => r,
+            };
+            self.signed(&mut sum, &format!("{}\\{}({})", radius, function, angle));
+        }
+        if sum.is_empty() {
+            sum.push('0');
+        }
+        sum
+    }
+
+    /// `c + sum radius e^(i (2 PI f t + phase))`
+    fn complex_sum(&self, constant: (f32, f32), terms: &[(f32, f32, f32)]) -> String {
+        let mut sum = String::new();
+        let (re, im) = (self.number(constant.0), self.number(constant.1));
+        match (re.as_str(), im.as_str()) {
+            ("0", "0") => {}
+            (_, "0") => sum.push_str(&re),
+            ("0", _) => sum.push_str(&format!("{}i", im)),
+            _ => {
+                let mut c = re.clone();
+                self.signed(&mut c, &format!("{}i", im));
+                sum.push_str(&format!("({})", c));
+            }
+        }
+        for &(frequency, radius, phase) in terms {
+            let exponent = match self.number(phase).as_str() {
+                "0" => self.rotation(frequency).replace("\\pi t", "\\pi i t"),
+                phase => {
+                    let mut angle = self.rotation(frequency);
+                    self.signed(&mut angle, phase);
+                    format!("i({})", angle)
+                }
+            };
+            let radius = match self.number(radius) {
+                r if r == "1" => String::new(),
+                r => r,
+            };
+            self.signed(&mut sum, &format!("{}e^{{{}}}", radius, exponent));
+        }
+        if sum.is_empty() {
+            sum.push('0');
+        }
+        sum
+    }
+
+    fn equations(&self, data: &[DrawData]) -> (String, usize) {
+        let series = series_terms(data, self.terms, self.y_up);
+        let (_, dc_radius, dc_phase) = series[0];
+        let constant = (dc_radius * dc_phase.cos(), dc_radius * dc_phase.sin());
+
+        let largest = series[1..].iter().map(|t| t.1.abs()).fold(0.0, f32::max);
+        let terms: Vec<(f32, f32, f32)> = series[1..]
+            .iter()
+            .copied()
+            .filter(|t| t.1.abs() >= self.tolerance * largest && self.number(t.1) != "0")
+            .collect();
+
+        let header = format!(
+            "Generated by fourier-svg: {} of {} rotating terms, rounded to {} decimals",
+            terms.len(),
+            series.len() - 1,
+            self.precision
+        );
+        let text = match self.format {
+            MathFormat::Latex => format!(
+                "% {}\n\\begin{{align*}}\n\
+                 x(t) &= {} \\\\\n\
+                 y(t) &= {}\n\
+                 \\end{{align*}}\n",
+                header,
+                self.trigonometric_sum(constant.0, &terms, "cos"),
+                self.trigonometric_sum(constant.1, &terms, "sin")
+            ),
+            MathFormat::LatexComplex => format!(
+                "% {}\n\\begin{{equation*}}\n\
+                 z(t) = {}\n\
+                 \\end{{equation*}}\n",
+                header,
+                self.complex_sum(constant, &terms)
+            ),
+            // Desmos takes no spaces around operators and no comments
+            MathFormat::Desmos => format!(
+                "f_{{x}}(t)={}\nf_{{y}}(t)={}\n(f_{{x}}(t),f_{{y}}(t))\n",
+                self.trigonometric_sum(constant.0, &terms, "cos")
+                    .replace(' ', "")
+                    .replace("\\pit", "\\pi t"),
+                self.trigonometric_sum(constant.1, &terms, "sin")
+                    .replace(' ', "")
+                    .replace("\\pit", "\\pi t"),
+            ),
+        };
+        (text, terms.len())
+    }
+}
+
+impl Default for MathVisualizer {
+    fn default() -> Self {
+        Self::new("output.tex".to_string())
+    }
+}
+
+impl Visualizer for MathVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        if data
+            .iter()
+            .any(|d| !(d.frequency.is_finite() && d.radius.is_finite() && d.angle.is_finite()))
+        {
+            return Err(VisualizerError::InvalidData(
+                "cannot write non-finite coefficients as equations".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let (text, _) = self.equations(&data);
+        let mut writer = CountingWriter::new(writer);
+        writer.write_all(text.as_bytes())?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounded_equations_without_negligible_terms() {
+        let data = vec![
+            DrawData::new(0.0, 2.0, 0.0),
+            DrawData::new(1.0, 10.0, 0.5),
+            DrawData::new(-1.0, 1.0, -0.25),
+            DrawData::new(2.0, 0.0001, 0.0),
+            DrawData::new(-2.0, 3.004, 0.0),
+        ];
+        let visualizer = MathVisualizer::default().with_precision(2);
+
+        let (latex, terms) = visualizer.equations(&data);
+        assert_eq!(terms, 3);
+        assert!(latex.contains(
+            "x(t) &= 2 + 10\\cos(2\\pi t + 0.5) + \\cos(-2\\pi t - 0.25) + 3\\cos(-4\\pi t) \\\\\n"
+        ));
+        assert!(latex.contains("y(t) &= 10\\sin(2\\pi t + 0.5)"));
+
+        let complex = visualizer.with_format(MathFormat::LatexComplex);
+        let (latex, _) = complex.equations(&data);
+        assert!(latex.contains(
+            "z(t) = 2 + 10e^{i(2\\pi t + 0.5)} + e^{i(-2\\pi t - 0.25)} + 3e^{-4\\pi i t}\n"
+        ));
+
+        let desmos = MathVisualizer::default()
+            .with_format(MathFormat::Desmos)
+            .with_tolerance(0.2);
+        let (text, terms) = desmos.equations(&data);
+        assert_eq!(terms, 2);
+        assert!(text.starts_with("f_{x}(t)=2+10\\cos(2\\pi t+0.5)+3\\cos(-4\\pi t)\n"));
+        assert!(text.ends_with("(f_{x}(t),f_{y}(t))\n"));
+    }
+}
@@ -1,12 +1,231 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use serde::Serialize;
+
+pub mod animated_svg_visualizer;
+pub mod animation;
+pub mod apng_visualizer;
+pub mod array_visualizer;
+pub mod code_visualizer;
 pub mod export_visualizer;
+mod frames;
+mod gif_palette;
 pub mod gif_visualizer;
 pub mod html_visualizer;
+pub mod math_visualizer;
+pub mod plotter_visualizer;
+pub mod png_sequence_visualizer;
+pub mod svg_visualizer;
+pub mod terminal_visualizer;
+pub mod webp_visualizer;
+pub mod y4m_visualizer;
 
 // Re-export commonly used types
 pub use export_visualizer::{
-    export_to_draw_data, load_fourier_export, ExportMetadata, FourierCoefficient, FourierExport,
+    export_to_draw_data, load_fourier_export, ExportLayer, ExportMetadata, FourierCoefficient,
+    FourierExport,
 };
 
+/// What a visualizer produced
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderSummary {
+    /// Path of the written file, `None` when rendered into a writer
+    pub output: Option<String>,
+    /// Size of the output in bytes
+    pub bytes: u64,
+    /// Number of animation frames written, 0 for outputs that are not frame based
+    pub frames: usize,
+}
+
+/// Errors raised while rendering a visualization
+#[derive(Debug)]
+pub enum VisualizerError {
+    /// The output could not be created or written
+    Io(std::io::Error),
+    /// An image or animation encoder failed
+    Encoding(String),
+    /// The data could not be serialised
+    Serialization(serde_json::Error),
+    /// The data or the visualizer settings cannot be rendered
+    InvalidData(String),
+}
+
+impl fmt::Display for VisualizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VisualizerError::Io(e) => write!(f, "I/O error: {}", e),
+            VisualizerError::Encoding(e) => write!(f, "Encoding error: {}", e),
+            VisualizerError::Serialization(e) => write!(f, "Serialization error: {}", e),
+            VisualizerError::InvalidData(e) => write!(f, "Invalid data: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VisualizerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VisualizerError::Io(e) => Some(e),
+            VisualizerError::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for VisualizerError {
+    fn from(e: std::io::Error) -> Self {
+        VisualizerError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for VisualizerError {
+    fn from(e: serde_json::Error) -> Self {
+        VisualizerError::Serialization(e)
+    }
+}
+
+impl From<gif::EncodingError> for VisualizerError {
+    fn from(e: gif::EncodingError) -> Self {
+        match e {
+            gif::EncodingError::Io(e) => VisualizerError::Io(e),
+            e => VisualizerError::Encoding(e.to_string()),
+        }
+    }
+}
+
+impl From<png::EncodingError> for VisualizerError {
+    fn from(e: png::EncodingError) -> Self {
+        match e {
+            png::EncodingError::IoError(e) => VisualizerError::Io(e),
+            e => VisualizerError::Encoding(e.to_string()),
+        }
+    }
+}
+
+impl From<image_webp::EncodingError> for VisualizerError {
+    fn from(e: image_webp::EncodingError) -> Self {
+        match e {
+            image_webp::EncodingError::IoError(e) => VisualizerError::Io(e),
+            e => VisualizerError::Encoding(e.to_string()),
+        }
+    }
+}
+
+/// Writer adapter counting the bytes going through it
+pub(crate) struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reject data that no visualizer can render
+pub(crate) fn check_data(data: &[crate::fft_drawer::DrawData]) -> Result<(), VisualizerError> {
+    if data.is_empty() {
+        return Err(VisualizerError::InvalidData(
+            "no Fourier coefficients to render".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Two terms drawing a small circle, for the tests of the visualizers
+#[cfg(test)]
+pub(crate) fn test_data() -> Vec<crate::fft_drawer::DrawData> {
+    use crate::fft_drawer::DrawData;
+    vec![DrawData::new(0.0, 10.0, 0.0), DrawData::new(1.0, 20.0, 0.0)]
+}
+
+/// Render `test_data` into memory
+#[cfg(test)]
+pub(crate) fn render_test_data(visualizer: &dyn Visualizer) -> (RenderSummary, Vec<u8>) {
+    let mut buffer = Vec::new();
+    let summary = visualizer.render_to(test_data(), &mut buffer).unwrap();
+    (summary, buffer)
+}
+
 pub trait Visualizer {
-    fn render(&self, data: Vec<crate::fft_drawer::DrawData>) -> bool;
+    /// Render into an arbitrary writer, e.g. a buffer or stdout
+    fn render_to(
+        &self,
+        data: Vec<crate::fft_drawer::DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError>;
+
+    /// File written by `render`
+    fn file_name(&self) -> &str;
+
+    /// Check that `data` and the settings can be rendered before any output is created
+    fn check(&self, data: &[crate::fft_drawer::DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)
+    }
+
+    /// Render into the file given at construction
+    fn render(
+        &self,
+        data: Vec<crate::fft_drawer::DrawData>,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let mut writer = BufWriter::new(File::create(self.file_name())?);
+        let mut summary = self.render_to(data, &mut writer)?;
+        writer.flush()?;
+
+        summary.output = Some(self.file_name().to_string());
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_data_is_rejected() {
+        let visualizer = gif_visualizer::GIFVisualizer::new("unused.gif".to_string());
+        match visualizer.render(Vec::new()) {
+            Err(VisualizerError::InvalidData(_)) => {}
+            other => panic!("expected InvalidData, got {:?}", other),
+        }
+        assert!(!std::path::Path::new("unused.gif").exists());
+    }
+
+    #[test]
+    fn test_render_into_memory() {
+        let data = vec![
+            crate::DrawData::new(0.0, 100.0, 0.5),
+            crate::DrawData::new(1.0, 50.0, 0.0),
+        ];
+        let visualizer = gif_visualizer::GIFVisualizer::default()
+            .with_dimensions(64, 48)
+            .with_frames(3);
+
+        let mut buffer = Vec::new();
+        let summary = visualizer.render_to(data, &mut buffer).unwrap();
+        assert_eq!(summary.output, None);
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.bytes, buffer.len() as u64);
+        assert!(buffer.starts_with(b"GIF89a"));
+    }
 }
@@ -0,0 +1,430 @@
+//! Plotter - The reconstructed curve as G-code or HPGL
+//!
+//! Every curve is sampled over one period and scaled to fit a box given in
+//! millimetres, with y flipped to point up as on plotter beds. The box must
+//! lie on the bed, which is checked before anything is written.
+//!
+//! With several layers, the curves are drawn in a tour picking the closest
+//! curve from the pen position each time. As the curves are closed, each one
+//! starts at its point closest to the pen, leaving only short pen-up moves.
+//!
+//! G-code moves in absolute millimetres, travelling with `G1` at the travel
+//! rate so the rate is honoured, and lifts the pen with configurable
+//! commands (`M3`/`M5` for a laser, Z moves or a servo for a pen). HPGL
+//! moves in plotter units of 0.025 mm and sets the drawing speed with `VS`,
+//! pen-up moves being left at the plotter's speed.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::epicycles::Epicycles;
+use crate::fft_drawer::DrawData;
+use crate::framing::Bounds;
+use crate::processor::PathLayer;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// HPGL plotter units in a millimetre
+const HPGL_UNITS_PER_MM: f32 = 40.0;
+
+/// Commands driving the plotter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotterFormat {
+    GCode,
+    Hpgl,
+}
+
+impl PlotterFormat {
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            PlotterFormat::GCode => "gcode",
+            PlotterFormat::Hpgl => "hpgl",
+        }
+    }
+}
+
+pub struct PlotterVisualizer {
+    file_name: String,
+    format: PlotterFormat,
+    size: (f32, f32),
+    origin: (f32, f32),
+    bed: (f32, f32),
+    feed_rate: f32,
+    travel_rate: f32,
+    pen_up: String,
+    pen_down: String,
+    samples: usize,
+    terms: Option<usize>,
+    layers: Vec<PathLayer>,
+}
+
+impl PlotterVisualizer {
+    pub fn new(file_name: String) -> PlotterVisualizer {
+        PlotterVisualizer {
+            file_name,
+            format: PlotterFormat::GCode,
+            size: (180.0, 180.0),
+            origin: (10.0, 10.0),
+            bed: (200.0, 200.0),
+            feed_rate: 1500.0,
+            travel_rate: 3000.0,
+            pen_up: "G0 Z5".to_string(),
+            pen_down: "G1 Z0".to_string(),
+            samples: 1000,
+            terms: None,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn with_format(mut self, format: PlotterFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Box in millimetres the drawing is fitted in, keeping its proportions
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Lower left corner of the box on the bed, in millimetres
+    pub fn with_origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = (x, y);
+        self
+    }
+
+    /// Reachable area in millimetres from the home position
+    pub fn with_bed(mut self, width: f32, height: f32) -> Self {
+        self.bed = (width, height);
+        self
+    }
+
+    /// Speeds in mm/min with the pen down and up
+    pub fn with_feed_rates(mut self, feed_rate: f32, travel_rate: f32) -> Self {
+        self.feed_rate = feed_rate;
+        self.travel_rate = travel_rate;
+        self
+    }
+
+    /// G-code lines lifting and lowering the pen
+    pub fn with_pen_commands(mut self, pen_up: String, pen_down: String) -> Self {
+        self.pen_up = pen_up;
+        self.pen_down = pen_down;
+        self
+    }
+
+    /// Points of every curve over one period
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Only draw with the first `terms` rotating terms
+    pub fn with_terms(mut self, terms: usize) -> Self {
+        self.terms = Some(terms);
+        self
+    }
+
+    /// Draw the visible layers instead of the data
+    pub fn with_layers(mut self, layers: Vec<PathLayer>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Reject settings and data that cannot be planned
+    fn check_options(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        if self.layers.is_empty() {
+            check_data(data)?;
+        }
+        let positive = |v: f32| v.is_finite() && v > 0.0;
+        if !(positive(self.size.0)
+            && positive(self.size.1)
+            && positive(self.feed_rate)
+            && positive(self.travel_rate)
+            && self.samples >= 2)
+        {
+            return Err(VisualizerError::InvalidData(format!(
+                "cannot plot {} points in {}x{} mm at {} and {} mm/min",
+                self.samples, self.size.0, self.size.1, self.feed_rate, self.travel_rate
+            )));
+        }
+        Ok(())
+    }
+
+    /// Closed curves in millimetres on the bed, in drawing order
+    fn plan(&self, data: &[DrawData]) -> Result<Vec<Vec<(f32, f32)>>, VisualizerError> {
+        let sources: Vec<&[DrawData]> = match self.layers.is_empty() {
+            true => vec![data],
+            false => self
+                .layers
+                .iter()
+                .filter(|layer| layer.visible && !layer.fourier_data.is_empty())
+                .map(|layer| layer.fourier_data.as_slice())
+                .collect(),
+        };
+        let curves: Vec<Vec<(f32, f32)>> = sources
+            .into_iter()
+            .map(|data| {
+                let mut epicycles = Epicycles::new(data);
+                if let Some(terms) = self.terms {
+                    epicycles = epicycles.truncated(terms);
+                }
+                epicycles.trace(self.samples)
+            })
+            .collect();
+
+        let mut bounds = Bounds::empty();
+        for point in curves.iter().flatten() {
+            bounds.include(*point);
+        }
+        if bounds.is_empty()
+            || curves
+                .iter()
+                .flatten()
+                .any(|(x, y)| !x.is_finite() || !y.is_finite())
+        {
+            return Err(VisualizerError::InvalidData(
+                "the curve has no finite points to plot".to_string(),
+            ));
+        }
+
+        // Fit and center in the box, y pointing up
+        let scale = (self.size.0 / bounds.width().max(f32::EPSILON))
+            .min(self.size.1 / bounds.height().max(f32::EPSILON));
+        let left = self.origin.0 + (self.size.0 - bounds.width() * scale) / 2.0;
+        let bottom = self.origin.1 + (self.size.1 - bounds.height() * scale) / 2.0;
+        let curves: Vec<Vec<(f32, f32)>> = curves
+            .into_iter()
+            .map(|curve| {
+                curve
+                    .into_iter()
+                    .map(|(x, y)| {
+                        (
+                            left + (x - bounds.min_x) * scale,
+                            bottom + (bounds.max_y - y) * scale,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let (right, top) = (
+            left + bounds.width() * scale,
+            bottom + bounds.height() * scale,
+        );
+        // Rounding of the scaled points is tolerated
+        let margin = 1e-3;
+        if left < -margin
+            || bottom < -margin
+            || right > self.bed.0 + margin
+            || top > self.bed.1 + margin
+        {
+            return Err(VisualizerError::InvalidData(format!(
+                "the drawing spans x {:.1} to {:.1} mm and y {:.1} to {:.1} mm, \
+                 outside the {}x{} mm bed",
+                left, right, bottom, top, self.bed.0, self.bed.1
+            )));
+        }
+
+        Ok(tour(curves))
+    }
+
+    /// Write the planned curves in the chosen format
+    fn write_curves(
+        &self,
+        curves: &[Vec<(f32, f32)>],
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        let mut writer = CountingWriter::new(writer);
+        match self.format {
+            PlotterFormat::GCode => self.gcode(curves, &mut writer)?,
+            PlotterFormat::Hpgl => self.hpgl(curves, &mut writer)?,
+        }
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+
+    fn gcode(&self, curves: &[Vec<(f32, f32)>], writer: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "; Generated by fourier-svg, curves: {}",
+            curves.len()
+        )?;
+        writeln!(writer, "G21 ; millimetres")?;
+        writeln!(writer, "G90 ; absolute positions")?;
+        writeln!(writer, "{}", self.pen_up)?;
+        for curve in curves {
+            let (x, y) = curve[0];
+            writeln!(writer, "G1 X{:.3} Y{:.3} F{}", x, y, self.travel_rate)?;
+            writeln!(writer, "{}", self.pen_down)?;
+            for (i, (x, y)) in curve.iter().skip(1).enumerate() {
+                match i {
+                    0 => writeln!(writer, "G1 X{:.3} Y{:.3} F{}", x, y, self.feed_rate)?,
+                    _ => writeln!(writer, "G1 X{:.3} Y{:.3}", x, y)?,
+                }
+            }
+            writeln!(writer, "{}", self.pen_up)?;
+        }
+        writeln!(writer, "G1 X0 Y0 F{}", self.travel_rate)?;
+        writeln!(writer, "M2")
+    }
+
+    fn hpgl(&self, curves: &[Vec<(f32, f32)>], writer: &mut dyn Write) -> std::io::Result<()> {
+        let unit = |(x, y): (f32, f32)| {
+            format!(
+                "{},{}",
+                (x * HPGL_UNITS_PER_MM).round() as i32,
+                (y * HPGL_UNITS_PER_MM).round() as i32
+            )
+        };
+        // VS takes centimetres per second
+        write!(writer, "IN;SP1;VS{:.1};", self.feed_rate / 600.0)?;
+        for curve in curves {
+            let points: Vec<String> = curve.iter().skip(1).map(|p| unit(*p)).collect();
+            write!(writer, "\nPU{};PD{};", unit(curve[0]), points.join(","))?;
+        }
+        writeln!(writer, "\nPU0,0;SP0;")
+    }
+}
+
+/// Order the closed curves from the origin, each one picked and started at
+/// its point closest to the pen, and closed back to that point
+fn tour(mut curves: Vec<Vec<(f32, f32)>>) -> Vec<Vec<(f32, f32)>> {
+    let distance = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2);
+    let mut pen = (0.0, 0.0);
+    let mut ordered = Vec::with_capacity(curves.len());
+
+    while !curves.is_empty() {
+        let (curve, start) = curves
+            .iter()
+            .enumerate()
+            .flat_map(|(c, curve)| curve.iter().enumerate().map(move |(i, p)| (c, i, *p)))
+            .min_by(|a, b| distance(pen, a.2).total_cmp(&distance(pen, b.2)))
+            .map(|(c, i, _)| (c, i))
+            .unwrap_or((0, 0));
+
+        let mut points = curves.swap_remove(curve);
+        if points.is_empty() {
+            continue;
+        }
+        points.rotate_left(start);
+        points.push(points[0]);
+        pen = points[0];
+        ordered.push(points);
+    }
+    ordered
+}
+
+impl Default for PlotterVisualizer {
+    fn default() -> Self {
+        Self::new("output.gcode".to_string())
+    }
+}
+
+impl Visualizer for PlotterVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Plans the drawing too, so a plot off the bed fails before any file is
+    /// created
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        self.check_options(data)?;
+        self.plan(data).map(|_| ())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check_options(&data)?;
+        let curves = self.plan(&data)?;
+        self.write_curves(&curves, writer)
+    }
+
+    /// Planned once, before the file is created
+    fn render(&self, data: Vec<DrawData>) -> Result<RenderSummary, VisualizerError> {
+        self.check_options(&data)?;
+        let curves = self.plan(&data)?;
+
+        let mut writer = BufWriter::new(File::create(&self.file_name)?);
+        let mut summary = self.write_curves(&curves, &mut writer)?;
+        writer.flush()?;
+
+        summary.output = Some(self.file_name.clone());
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, radius: f32) -> Vec<DrawData> {
+        vec![DrawData::new(0.0, x, 0.0), DrawData::new(1.0, radius, 0.0)]
+    }
+
+    #[test]
+    fn test_plot_fits_the_box_and_checks_the_bed() {
+        let visualizer = PlotterVisualizer::default()
+            .with_size(100.0, 50.0)
+            .with_origin(0.0, 0.0)
+            .with_samples(8);
+
+        let mut buffer = Vec::new();
+        visualizer
+            .render_to(circle(0.0, 10.0), &mut buffer)
+            .unwrap();
+        let gcode = String::from_utf8(buffer).unwrap();
+        // A 50 mm circle centered in the box, from its point closest to home
+        assert!(gcode.contains("G1 X32.322 Y7.322 F3000\nG1 Z0\nG1 X25.000 Y25.000 F1500\n"));
+        assert!(gcode.contains("G1 X75.000 Y25.000\n"));
+        assert_eq!(gcode.matches("G1 Z0").count(), 1);
+        assert!(gcode.ends_with("G1 X0 Y0 F3000\nM2\n"));
+
+        let mut buffer = Vec::new();
+        visualizer
+            .with_format(PlotterFormat::Hpgl)
+            .render_to(circle(0.0, 10.0), &mut buffer)
+            .unwrap();
+        let hpgl = String::from_utf8(buffer).unwrap();
+        assert!(hpgl.starts_with("IN;SP1;VS2.5;\nPU1293,293;PD1000,1000,"));
+
+        let too_large = PlotterVisualizer::default().with_bed(100.0, 100.0);
+        match too_large.check(&circle(0.0, 10.0)) {
+            Err(VisualizerError::InvalidData(message)) => assert!(message.contains("100x100")),
+            other => panic!("expected InvalidData, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tour_starts_each_curve_near_the_pen() {
+        let layer = |id: &str, data: Vec<DrawData>| PathLayer {
+            id: id.to_string(),
+            path_data: String::new(),
+            fourier_data: data,
+            visible: true,
+            opacity: 1.0,
+        };
+        let visualizer = PlotterVisualizer::default()
+            .with_samples(4)
+            .with_layers(vec![
+                layer("far", circle(100.0, 10.0)),
+                layer("near", circle(0.0, 10.0)),
+            ]);
+
+        let curves = visualizer.plan(&[]).unwrap();
+        assert_eq!(curves.len(), 2);
+        // The near circle first from its bottom, then the far one from its
+        // left, each closed
+        assert_eq!(curves[0][0], (25.0, 85.0));
+        assert_eq!(curves[1][0], (160.0, 100.0));
+        assert_eq!(curves[0].first(), curves[0].last());
+        assert_eq!(curves[0].len(), 5);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use image::RgbaImage;
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::animation::Animation;
+use crate::visualizer::frames::render_in_order;
+use crate::visualizer::{check_data, RenderSummary, Visualizer, VisualizerError};
+
+/// Numbered PNG frames for video editors and external encoders
+///
+/// `render` writes `frame_0000.png`, `frame_0001.png`, ... into the
+/// directory given at construction, replacing the frames of an earlier
+/// render so none are left over, while `render_to` writes the PNG files
+/// back to back, as read by `ffmpeg -f image2pipe`.
+pub struct PNGSequenceVisualizer {
+    directory: String,
+    animation: Animation,
+}
+
+impl PNGSequenceVisualizer {
+    pub fn new(directory: String) -> PNGSequenceVisualizer {
+        PNGSequenceVisualizer {
+            directory,
+            animation: Animation::default(),
+        }
+    }
+
+    /// Frames to draw, one file each, without timing
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    /// Render every frame in order, handing the encoded PNG files to `write`
+    /// along with their index and the number of frames
+    fn render_frames(
+        &self,
+        data: &[DrawData],
+        mut write: impl FnMut(usize, usize, &[u8]) -> Result<(), VisualizerError>,
+    ) -> Result<usize, VisualizerError> {
+        let animation = self.animation.prepare(data);
+        let frames = animation.frame_count();
+        let mut index = 0;
+        render_in_order(
+            frames,
+            |frame| encode_png(&animation.render(frame)),
+            |png| {
+                write(index, frames, &png?)?;
+                index += 1;
+                Ok(())
+            },
+        )?;
+        Ok(frames)
+    }
+}
+
+impl Default for PNGSequenceVisualizer {
+    fn default() -> Self {
+        Self::new("frames".to_string())
+    }
+}
+
+/// Still PNG image of a frame
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, VisualizerError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// Whether `name` is the name of a frame file
+fn is_frame_file_name(name: &str) -> bool {
+    name.strip_prefix("frame_")
+        .and_then(|name| name.strip_suffix(".png"))
+        .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Name of a frame file, zero-padded so the files sort in order
+fn frame_file_name(index: usize, frames: usize) -> String {
+    let digits = frames.saturating_sub(1).to_string().len().max(4);
+    format!("frame_{:0digits$}.png", index, digits = digits)
+}
+
+impl Visualizer for PNGSequenceVisualizer {
+    /// Directory receiving the frames
+    fn file_name(&self) -> &str {
+        &self.directory
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let mut bytes = 0;
+        let frames = self.render_frames(&data, |_, _, png| {
+            writer.write_all(png)?;
+            bytes += png.len() as u64;
+            Ok(())
+        })?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes,
+            frames,
+        })
+    }
+
+    fn render(&self, data: Vec<DrawData>) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let directory = Path::new(&self.directory);
+        fs::create_dir_all(directory)?;
+        // Frames past the new last one would end up in the video
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            if entry.file_name().to_str().is_some_and(is_frame_file_name) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        let mut bytes = 0;
+        let frames = self.render_frames(&data, |index, frames, png| {
+            fs::write(directory.join(frame_file_name(index, frames)), png)?;
+            bytes += png.len() as u64;
+            Ok(())
+        })?;
+
+        Ok(RenderSummary {
+            output: Some(self.directory.clone()),
+            bytes,
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::{render_test_data, test_data};
+
+    #[test]
+    fn test_frame_file_names_sort_in_order() {
+        assert_eq!(frame_file_name(7, 100), "frame_0007.png");
+        assert_eq!(frame_file_name(7, 10000), "frame_0007.png");
+        assert_eq!(frame_file_name(7, 10001), "frame_00007.png");
+
+        let visualizer = PNGSequenceVisualizer::default()
+            .with_animation(Animation::default().with_dimensions(20, 10).with_frames(3));
+        let (summary, stream) = render_test_data(&visualizer);
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.bytes, stream.len() as u64);
+        let signature = b"\x89PNG\r\n\x1a\n";
+        let files = stream.windows(8).filter(|w| w == signature).count();
+        assert_eq!(files, 3);
+    }
+
+    #[test]
+    fn test_render_replaces_earlier_frames() {
+        let directory = std::env::temp_dir().join("fourier_svg_png_sequence");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("notes.txt"), "kept").unwrap();
+
+        let render = |frames| {
+            PNGSequenceVisualizer::new(directory.to_str().unwrap().to_string())
+                .with_animation(
+                    Animation::default()
+                        .with_dimensions(8, 8)
+                        .with_frames(frames),
+                )
+                .render(test_data())
+                .unwrap()
+        };
+        render(5);
+        assert_eq!(render(2).frames, 2);
+
+        let mut names: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["frame_0000.png", "frame_0001.png", "notes.txt"]);
+        assert!(!is_frame_file_name("frame_.png") && !is_frame_file_name("frame_01.jpg"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+//! Static SVG - Vector image of the reconstructed drawing
+//!
+//! The drawing traced by the first K epicycles is written as a `<path>`,
+//! optionally over the original path and with the epicycles shown at a given
+//! time. Several values of K make a grid comparing the reconstructions.
+//!
+//! The drawing keeps the coordinates of the source SVG, so giving its
+//! `viewBox` (see `extract_view_box_from_file`) lines the output up with the
+//! original document. Line widths are in pixels whatever the scale.
+
+use std::io::Write;
+
+use svg::node::element::{Circle, Group, Line, Path, Rectangle, Text, SVG};
+use svg::node::Text as TextNode;
+use svg::Document;
+
+use crate::epicycles::Epicycles;
+use crate::fft_drawer::DrawData;
+use crate::framing::{Bounds, BOUNDS_SAMPLES};
+use crate::theme::{Color, Theme};
+use crate::visualizer::{CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Margin added around the drawing when no view box is given, relative to its size
+const AUTO_MARGIN: f32 = 0.05;
+
+/// Height of the label under each cell of a grid, relative to the cell height
+const LABEL_HEIGHT: f32 = 0.1;
+
+pub struct SVGVisualizer {
+    file_name: String,
+    terms: Vec<usize>,
+    columns: Option<usize>,
+    original: Option<String>,
+    view_box: Option<Bounds>,
+    circles_at: Option<f32>,
+    samples: usize,
+    theme: Theme,
+}
+
+impl SVGVisualizer {
+    pub fn new(file_name: String) -> SVGVisualizer {
+        SVGVisualizer {
+            file_name,
+            terms: Vec::new(),
+            columns: None,
+            original: None,
+            view_box: None,
+            circles_at: None,
+            samples: 1024,
+            theme: Theme::default(),
+        }
+    }
+
+    /// Number of epicycles drawing the reconstruction, all of them by default.
+    /// Several counts are drawn side by side in a grid, labelled with the count.
+    pub fn with_terms(mut self, terms: Vec<usize>) -> Self {
+        self.terms = terms;
+        self
+    }
+
+    /// Cells per row of the grid, about as many as rows by default
+    pub fn with_columns(mut self, columns: usize) -> Self {
+        self.columns = Some(columns.max(1));
+        self
+    }
+
+    /// Path data of the original drawing, shown under the reconstruction
+    pub fn with_original(mut self, path_data: String) -> Self {
+        self.original = Some(path_data);
+        self
+    }
+
+    /// Area of the drawing in SVG units, usually the viewBox of the source file.
+    /// Without one, the output is fitted around the reconstruction.
+    pub fn with_view_box(mut self, view_box: Bounds) -> Self {
+        self.view_box = Some(view_box);
+        self
+    }
+
+    /// Show the circles and arms at time `t`, in periods
+    pub fn with_circles_at(mut self, t: f32) -> Self {
+        self.circles_at = Some(t);
+        self
+    }
+
+    /// Points of the reconstructed path over one period
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Area drawn in every cell
+    fn area(&self, epicycles: &Epicycles) -> Bounds {
+        if let Some(view_box) = self.view_box {
+            return view_box;
+        }
+        let mut bounds = Bounds::empty();
+        for point in epicycles.trace(self.samples.max(BOUNDS_SAMPLES)) {
+            bounds.include(point);
+        }
+        if let Some(t) = self.circles_at {
+            for joint in epicycles.joints(t) {
+                bounds.include(joint);
+            }
+        }
+        // Keep a visible area for degenerate drawings, e.g. a single point
+        let margin = (bounds.width().max(bounds.height()) * AUTO_MARGIN).max(1.0);
+        Bounds {
+            min_x: bounds.min_x - margin,
+            min_y: bounds.min_y - margin,
+            max_x: bounds.max_x + margin,
+            max_y: bounds.max_y + margin,
+        }
+    }
+
+    /// Reconstruction of `epicycles` with its overlays, in drawing coordinates
+    fn cell(&self, epicycles: &Epicycles) -> Group {
+        let theme = &self.theme;
+        let mut group = Group::new()
+            .set("fill", "none")
+            .set("stroke-linecap", "round")
+            .set("stroke-linejoin", "round");
+
+        if let Some(original) = &self.original {
+            group = group.add(
+                stroke(
+                    Path::new().set("d", original.as_str()),
+                    theme.trail,
+                    theme.trail_width,
+                )
+                .set("stroke-opacity", 0.3),
+            );
+        }
+
+        if let Some(t) = self.circles_at {
+            let joints = epicycles.joints(t);
+            for (arm, joint) in epicycles.arms().iter().zip(&joints) {
+                let circle = Circle::new()
+                    .set("cx", joint.0)
+                    .set("cy", joint.1)
+                    .set("r", arm.radius);
+                group = group.add(stroke(
+                    circle,
+                    theme.circle_color(arm.frequency),
+                    theme.circle_width,
+                ));
+            }
+            for (arm, pair) in epicycles.arms().iter().zip(joints.windows(2)) {
+                let line = Line::new()
+                    .set("x1", pair[0].0)
+                    .set("y1", pair[0].1)
+                    .set("x2", pair[1].0)
+                    .set("y2", pair[1].1);
+                group = group.add(stroke(
+                    line,
+                    theme.arm_color(arm.frequency),
+                    theme.arm_width,
+                ));
+            }
+        }
+
+        let trace = epicycles.trace(self.samples.max(2));
+        group.add(stroke(
+            Path::new().set("d", path_data(&trace)),
+            theme.trail_color(1.0),
+            theme.trail_width,
+        ))
+    }
+}
+
+impl Default for SVGVisualizer {
+    fn default() -> Self {
+        Self::new("output.svg".to_string())
+    }
+}
+
+/// Closed path through `points`
+pub(crate) fn path_data(points: &[(f32, f32)]) -> String {
+    let mut data = String::with_capacity(points.len() * 16);
+    for (i, (x, y)) in points.iter().enumerate() {
+        data.push_str(if i == 0 { "M" } else { " L" });
+        data.push_str(&format!("{} {}", number(*x), number(*y)));
+    }
+    data.push_str(" Z");
+    data
+}
+
+/// Coordinate rounded to a hundredth, without trailing zeros
+pub(crate) fn number(value: f32) -> String {
+    let rounded = format!("{:.2}", value);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// Stroke an element with a color and a width in pixels
+fn stroke<T: svg::Node>(element: T, color: Color, width: f32) -> T {
+    let mut element = element;
+    element.assign("stroke", color.to_string());
+    element.assign("stroke-width", width);
+    element.assign("vector-effect", "non-scaling-stroke");
+    element
+}
+
+impl Visualizer for SVGVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let epicycles = Epicycles::new(&data);
+        let terms = if self.terms.is_empty() {
+            vec![epicycles.arms().len()]
+        } else {
+            self.terms.clone()
+        };
+        let grid = terms.len() > 1;
+        let columns = self
+            .columns
+            .unwrap_or_else(|| (terms.len() as f32).sqrt().ceil() as usize)
+            .min(terms.len());
+        let rows = terms.len().div_ceil(columns);
+
+        let area = self.area(&epicycles);
+        let (cell_width, cell_height) = (area.width(), area.height());
+        let label_height = if grid {
+            cell_height * LABEL_HEIGHT
+        } else {
+            0.0
+        };
+        let (width, height) = (
+            cell_width * columns as f32,
+            (cell_height + label_height) * rows as f32,
+        );
+
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0.0, 0.0, width, height));
+        if !self.theme.background.is_transparent() {
+            document = document.add(
+                Rectangle::new()
+                    .set("width", "100%")
+                    .set("height", "100%")
+                    .set("fill", self.theme.background.to_string()),
+            );
+        }
+
+        for (i, &count) in terms.iter().enumerate() {
+            let truncated = epicycles.truncated(count);
+            let (x, y) = (
+                (i % columns) as f32 * cell_width,
+                (i / columns) as f32 * (cell_height + label_height),
+            );
+            let cell = SVG::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", cell_width)
+                .set("height", cell_height)
+                .set("viewBox", (area.min_x, area.min_y, cell_width, cell_height))
+                .add(self.cell(&truncated));
+            document = document.add(cell);
+
+            if grid {
+                let arms = truncated.arms().len();
+                let label = format!("{} {}", arms, if arms == 1 { "circle" } else { "circles" });
+                document = document.add(
+                    Text::new()
+                        .set("x", x + cell_width / 2.0)
+                        .set("y", y + cell_height + label_height * 0.7)
+                        .set("font-family", "sans-serif")
+                        .set("font-size", label_height * 0.6)
+                        .set("text-anchor", "middle")
+                        .set("fill", self.theme.arm.to_string())
+                        .add(TextNode::new(label)),
+                );
+            }
+        }
+
+        let mut writer = CountingWriter::new(writer);
+        svg::write(&mut writer, &document)?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_svg_path, FourierConfig};
+
+    #[test]
+    fn test_grid_of_term_counts() {
+        let square = "M 0 0 L 40 0 L 40 40 L 0 40 Z";
+        let data = process_svg_path(square, &FourierConfig::new(1024, 51));
+        let visualizer = SVGVisualizer::default()
+            .with_terms(vec![1, 5, 25, 1000])
+            .with_original(square.to_string())
+            .with_view_box(Bounds {
+                min_x: -10.0,
+                min_y: -10.0,
+                max_x: 50.0,
+                max_y: 50.0,
+            })
+            .with_circles_at(0.25);
+
+        let mut buffer = Vec::new();
+        let summary = visualizer.render_to(data, &mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert_eq!(summary.bytes, svg.len() as u64);
+
+        // A 2x2 grid of 60x60 cells, each with a label underneath
+        assert!(svg.contains(r#"viewBox="0 0 120 132""#));
+        assert_eq!(svg.matches(r#"viewBox="-10 -10 60 60""#).count(), 4);
+        assert!(svg.contains(">\n1 circle\n<") && svg.contains(">\n50 circles\n<"));
+        assert_eq!(svg.matches(square).count(), 4);
+        assert_eq!(svg.matches("<circle").count(), 1 + 5 + 25 + 50);
+    }
+}
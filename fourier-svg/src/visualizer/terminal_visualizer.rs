@@ -0,0 +1,345 @@
+//! Terminal - Epicycle animation drawn with Unicode characters
+//!
+//! Frames are rasterised like the other animated outputs, at one pixel per
+//! braille dot (2x4 dots per character) or per half block (1x2 blocks per
+//! character). A pixel is lit when it stands out from the background, and
+//! every character takes the average color of its lit pixels as a 24-bit ANSI
+//! color.
+//!
+//! A frame is a string of lines separated by `\r\n`, so it can be printed in
+//! raw mode. Written to a file, the frames follow each other with the cursor
+//! moved home in between, which plays them back when the file is printed.
+
+use std::io::Write;
+
+use image::{Rgba, RgbaImage};
+
+use crate::fft_drawer::DrawData;
+use crate::theme::Color;
+use crate::visualizer::animation::{Animation, PreparedAnimation};
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+/// Smallest difference to the background, out of 255, for a pixel to be lit
+const LIT_THRESHOLD: u8 = 48;
+
+/// Characters drawing the pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalMode {
+    /// Braille patterns, 2x4 pixels per character
+    Braille,
+    /// Upper and lower half blocks, 1x2 pixels per character
+    HalfBlock,
+}
+
+impl TerminalMode {
+    /// Pixels covered by a character, across and down
+    fn cell(self) -> (u32, u32) {
+        match self {
+            TerminalMode::Braille => (2, 4),
+            TerminalMode::HalfBlock => (1, 2),
+        }
+    }
+}
+
+pub struct TerminalVisualizer {
+    file_name: String,
+    animation: Animation,
+    columns: u32,
+    rows: u32,
+    mode: TerminalMode,
+    color: bool,
+}
+
+impl TerminalVisualizer {
+    pub fn new(file_name: String) -> TerminalVisualizer {
+        TerminalVisualizer {
+            file_name,
+            animation: Animation::default(),
+            columns: 80,
+            rows: 24,
+            mode: TerminalMode::Braille,
+            color: true,
+        }
+        .fit()
+    }
+
+    /// Frames to draw, sized by the characters of `with_size` instead
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self.fit()
+    }
+
+    /// Number of characters across and lines down
+    pub fn with_size(mut self, columns: u32, rows: u32) -> Self {
+        self.columns = columns;
+        self.rows = rows;
+        self.fit()
+    }
+
+    pub fn with_mode(mut self, mode: TerminalMode) -> Self {
+        self.mode = mode;
+        self.fit()
+    }
+
+    /// Color the characters with ANSI escape codes, else only draw the shapes
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Size the frames to the characters
+    fn fit(mut self) -> Self {
+        let (width, height) = self.mode.cell();
+        self.animation = self
+            .animation
+            .with_dimensions(self.columns * width, self.rows * height);
+        self
+    }
+
+    /// Frames of `data`, to be shown one after the other
+    pub fn frames(&self, data: &[DrawData]) -> TerminalFrames<'_> {
+        TerminalFrames {
+            visualizer: self,
+            animation: self.animation.prepare(data),
+            background: self.animation.theme().background,
+        }
+    }
+}
+
+impl Default for TerminalVisualizer {
+    fn default() -> Self {
+        Self::new("output.ans".to_string())
+    }
+}
+
+/// The frames of an animation turned into text
+pub struct TerminalFrames<'a> {
+    visualizer: &'a TerminalVisualizer,
+    animation: PreparedAnimation<'a>,
+    background: Color,
+}
+
+impl TerminalFrames<'_> {
+    /// Number of frames including the hold frames
+    pub fn frame_count(&self) -> usize {
+        self.animation.frame_count()
+    }
+
+    /// Lines of a frame, separated by `\r\n`
+    pub fn frame(&self, frame: usize) -> String {
+        let image = self.animation.render(frame);
+        let visualizer = self.visualizer;
+        let mut text = Text::new(visualizer.color, self.background);
+        for row in 0..visualizer.rows {
+            if row > 0 {
+                text.end_line();
+            }
+            for column in 0..visualizer.columns {
+                match visualizer.mode {
+                    TerminalMode::Braille => self.braille(&image, column, row, &mut text),
+                    TerminalMode::HalfBlock => self.half_block(&image, column, row, &mut text),
+                }
+            }
+        }
+        text.finish()
+    }
+
+    /// Whether a pixel stands out from the background
+    fn lit(&self, pixel: &Rgba<u8>) -> bool {
+        let background = self.background;
+        if background.is_transparent() {
+            return pixel[3] >= LIT_THRESHOLD;
+        }
+        [background.r, background.g, background.b]
+            .iter()
+            .zip(pixel.0)
+            .any(|(b, p)| b.abs_diff(p) >= LIT_THRESHOLD)
+    }
+
+    fn braille(&self, image: &RgbaImage, column: u32, row: u32, text: &mut Text) {
+        // Bit of each dot, by column then line
+        const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+        let mut bits = 0;
+        let mut lit = Vec::with_capacity(8);
+        for (dx, dots) in DOTS.iter().enumerate() {
+            for (dy, bit) in dots.iter().enumerate() {
+                let pixel = image.get_pixel(column * 2 + dx as u32, row * 4 + dy as u32);
+                if self.lit(pixel) {
+                    bits |= bit;
+                    lit.push(*pixel);
+                }
+            }
+        }
+        match char::from_u32(0x2800 + bits) {
+            Some(c) if bits != 0 => text.push(c, Some(average(&lit)), None),
+            _ => text.push(' ', None, None),
+        }
+    }
+
+    fn half_block(&self, image: &RgbaImage, column: u32, row: u32, text: &mut Text) {
+        let top = image.get_pixel(column, row * 2);
+        let bottom = image.get_pixel(column, row * 2 + 1);
+        match (self.lit(top), self.lit(bottom)) {
+            // Opaque backgrounds are drawn as they are
+            _ if self.visualizer.color && !self.background.is_transparent() => {
+                text.push('▀', Some(*top), Some(*bottom))
+            }
+            (true, true) => text.push('▀', Some(*top), Some(*bottom)),
+            (true, false) => text.push('▀', Some(*top), None),
+            (false, true) => text.push('▄', Some(*bottom), None),
+            (false, false) => text.push(' ', None, None),
+        }
+    }
+}
+
+/// Average color of lit pixels
+fn average(pixels: &[Rgba<u8>]) -> Rgba<u8> {
+    let count = pixels.len().max(1) as u32;
+    let channel = |i: usize| (pixels.iter().map(|p| p[i] as u32).sum::<u32>() / count) as u8;
+    Rgba([channel(0), channel(1), channel(2), 255])
+}
+
+/// Characters and the escape codes coloring them, only written when they
+/// change from one character to the next
+struct Text {
+    text: String,
+    color: bool,
+    /// Background of the characters without one, `None` for the terminal's
+    background: Option<Rgba<u8>>,
+    foreground_set: Option<Rgba<u8>>,
+    background_set: Option<Option<Rgba<u8>>>,
+}
+
+impl Text {
+    fn new(color: bool, background: Color) -> Text {
+        Text {
+            text: String::new(),
+            color,
+            background: (!background.is_transparent()).then_some(Rgba([
+                background.r,
+                background.g,
+                background.b,
+                255,
+            ])),
+            foreground_set: None,
+            background_set: None,
+        }
+    }
+
+    fn push(&mut self, c: char, foreground: Option<Rgba<u8>>, background: Option<Rgba<u8>>) {
+        if self.color {
+            let background = background.or(self.background);
+            if self.background_set != Some(background) {
+                match background {
+                    Some(b) => self
+                        .text
+                        .push_str(&format!("\x1b[48;2;{};{};{}m", b[0], b[1], b[2])),
+                    None => self.text.push_str("\x1b[49m"),
+                }
+                self.background_set = Some(background);
+            }
+            if let Some(f) = foreground.filter(|f| self.foreground_set != Some(*f)) {
+                self.text
+                    .push_str(&format!("\x1b[38;2;{};{};{}m", f[0], f[1], f[2]));
+                self.foreground_set = Some(f);
+            }
+        }
+        self.text.push(c);
+    }
+
+    /// Reset the colors so they don't run to the edge of the terminal
+    fn end_line(&mut self) {
+        self.reset();
+        self.text.push_str("\r\n");
+    }
+
+    fn reset(&mut self) {
+        if self.color {
+            self.text.push_str("\x1b[0m");
+            self.foreground_set = None;
+            self.background_set = None;
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.reset();
+        self.text
+    }
+}
+
+impl Visualizer for TerminalVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let frames = self.frames(&data);
+        let mut writer = CountingWriter::new(writer);
+        // Clear the screen once, then draw every frame from the top left corner
+        writer.write_all(b"\x1b[2J")?;
+        for frame in 0..frames.frame_count() {
+            writer.write_all(b"\x1b[H")?;
+            writer.write_all(frames.frame(frame).as_bytes())?;
+        }
+        writer.write_all(b"\r\n")?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames: frames.frame_count(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Theme;
+
+    #[test]
+    fn test_frames_fill_the_characters() {
+        let data = vec![DrawData::new(0.0, 0.0, 0.0), DrawData::new(1.0, 10.0, 0.0)];
+        let animation = Animation::default()
+            .with_frames(4)
+            .with_theme(Theme::preset("dark").unwrap());
+
+        for mode in [TerminalMode::Braille, TerminalMode::HalfBlock] {
+            let visualizer = TerminalVisualizer::default()
+                .with_animation(animation.clone())
+                .with_size(30, 10)
+                .with_mode(mode)
+                .with_color(false);
+            let frames = visualizer.frames(&data);
+            assert_eq!(frames.frame_count(), 4);
+
+            let frame = frames.frame(1);
+            assert!(!frame.contains('\x1b'));
+            let lines: Vec<&str> = frame.split("\r\n").collect();
+            assert_eq!(lines.len(), 10);
+            assert!(lines.iter().all(|line| line.chars().count() == 30));
+            assert!(frame.chars().any(|c| c != ' ' && c != '\r' && c != '\n'));
+        }
+
+        // Colored frames only differ by their escape codes
+        let visualizer = TerminalVisualizer::default()
+            .with_animation(animation)
+            .with_size(30, 10);
+        let frame = visualizer.frames(&data).frame(1);
+        assert!(frame.contains("\x1b[38;2;") && frame.ends_with("\x1b[0m"));
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::Write;
+
+use image_webp::{ColorType, WebPEncoder};
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::animation::Animation;
+use crate::visualizer::frames::render_in_order;
+use crate::visualizer::{check_data, RenderSummary, Visualizer, VisualizerError};
+
+/// Length of the `RIFF <size> WEBP` header of a still WebP image
+const RIFF_HEADER_LEN: usize = 12;
+
+/// Animated WebP with lossless RGBA frames and millisecond timing
+///
+/// Each frame is encoded as a still lossless (VP8L) image, then wrapped in
+/// an `ANMF` chunk of the extended WebP container.
+pub struct WebPVisualizer {
+    file_name: String,
+    animation: Animation,
+}
+
+impl WebPVisualizer {
+    pub fn new(file_name: String) -> WebPVisualizer {
+        WebPVisualizer {
+            file_name,
+            animation: Animation::default(),
+        }
+    }
+
+    /// Frames to draw, the delay in milliseconds
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+}
+
+impl Default for WebPVisualizer {
+    fn default() -> Self {
+        Self::new("output.webp".to_string())
+    }
+}
+
+/// Append a RIFF chunk, padded to an even size
+fn push_chunk(out: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// Little-endian 24-bit field of the WebP container
+fn push_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+impl Visualizer for WebPVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()?;
+        // Canvas and frame sizes are stored on 24 bits, the VP8L bitstream
+        // limits them further to 14 bits
+        if self.animation.width() > 16384 || self.animation.height() > 16384 {
+            return Err(VisualizerError::InvalidData(format!(
+                "WebP frames cannot be {}x{} pixels",
+                self.animation.width(),
+                self.animation.height()
+            )));
+        }
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let (width, height) = (self.animation.width(), self.animation.height());
+        let animation = self.animation.prepare(&data);
+        let frames = animation.frame_count();
+        let background = self.animation.theme().background;
+
+        let mut body = Vec::new();
+
+        // Animated, with alpha
+        let mut vp8x = vec![0x02 | 0x10, 0, 0, 0];
+        push_u24(&mut vp8x, width - 1);
+        push_u24(&mut vp8x, height - 1);
+        push_chunk(&mut body, b"VP8X", &vp8x);
+
+        // Background color in BGRA order and an infinite loop
+        let anim = [background.b, background.g, background.r, background.a, 0, 0];
+        push_chunk(&mut body, b"ANIM", &anim);
+
+        let delay_ms = self.animation.delay_ms().min(0xFF_FFFF);
+        render_in_order(
+            frames,
+            |frame| {
+                let pixels = animation.render(frame).into_raw();
+                let mut still = Vec::new();
+                WebPEncoder::new(&mut still).encode(&pixels, width, height, ColorType::Rgba8)?;
+                Ok(still)
+            },
+            |still: Result<Vec<u8>, VisualizerError>| {
+                let mut anmf = Vec::new();
+                // Frame at the origin covering the canvas
+                push_u24(&mut anmf, 0);
+                push_u24(&mut anmf, 0);
+                push_u24(&mut anmf, width - 1);
+                push_u24(&mut anmf, height - 1);
+                push_u24(&mut anmf, delay_ms);
+                // Replace the canvas instead of blending over the last frame
+                anmf.push(0x02);
+                // The VP8L chunk of the still image
+                anmf.extend_from_slice(&still?[RIFF_HEADER_LEN..]);
+                push_chunk(&mut body, b"ANMF", &anmf);
+                Ok(())
+            },
+        )?;
+
+        let mut header = Vec::with_capacity(RIFF_HEADER_LEN);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        header.extend_from_slice(b"WEBP");
+        writer.write_all(&header)?;
+        writer.write_all(&body)?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: (header.len() + body.len()) as u64,
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::render_test_data;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_animated_webp_decodes() {
+        let animation = Animation::default().with_dimensions(40, 30).with_frames(4);
+        let visualizer = WebPVisualizer::default().with_animation(animation);
+
+        let (summary, buffer) = render_test_data(&visualizer);
+        assert_eq!(summary.frames, 4);
+        assert_eq!(summary.bytes, buffer.len() as u64);
+
+        let mut decoder = image_webp::WebPDecoder::new(Cursor::new(buffer)).unwrap();
+        assert!(decoder.is_animated());
+        assert_eq!(decoder.num_frames(), 4);
+        assert_eq!(decoder.dimensions(), (40, 30));
+        let mut frame = vec![0; decoder.output_buffer_size().unwrap()];
+        assert_eq!(decoder.read_frame(&mut frame).unwrap(), 20);
+    }
+}
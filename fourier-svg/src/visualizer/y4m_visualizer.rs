@@ -0,0 +1,164 @@
+//! Y4M - Uncompressed YUV 4:2:0 video stream
+//!
+//! YUV4MPEG2 is read by most video encoders, e.g.
+//! `fourier-svg -f shape.svg -b y4m -o - | ffmpeg -i - out.mp4`, so videos
+//! can be made without the library depending on an encoder.
+//!
+//! Colors are converted with the BT.601 coefficients in limited range, and
+//! the chroma planes hold the average of each 2x2 block of pixels. The stream
+//! has no alpha: a transparent background is rendered black.
+
+use std::io::Write;
+
+use image::RgbaImage;
+
+use crate::fft_drawer::DrawData;
+use crate::visualizer::animation::Animation;
+use crate::visualizer::frames::render_in_order;
+use crate::visualizer::{check_data, CountingWriter, RenderSummary, Visualizer, VisualizerError};
+
+pub struct Y4MVisualizer {
+    file_name: String,
+    animation: Animation,
+}
+
+impl Y4MVisualizer {
+    pub fn new(file_name: String) -> Y4MVisualizer {
+        Y4MVisualizer {
+            file_name,
+            animation: Animation::default(),
+        }
+    }
+
+    /// Frames to draw, at the frame rate of the animation
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+}
+
+impl Default for Y4MVisualizer {
+    fn default() -> Self {
+        Self::new("output.y4m".to_string())
+    }
+}
+
+/// Y, U and V planes of a frame, one after the other
+fn yuv420(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    // Composite over black
+    let rgb: Vec<[i32; 3]> = image
+        .pixels()
+        .map(|p| {
+            let a = p[3] as i32;
+            [0, 1, 2].map(|i| (p[i] as i32 * a + 127) / 255)
+        })
+        .collect();
+
+    let mut yuv = Vec::with_capacity(width * height * 3 / 2);
+    yuv.extend(
+        rgb.iter()
+            .map(|[r, g, b]| (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8),
+    );
+
+    let mut u = Vec::with_capacity(width * height / 4);
+    let mut v = Vec::with_capacity(width * height / 4);
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            let [r, g, b] = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|(dx, dy)| rgb[(y + dy) * width + x + dx])
+                .fold([0; 3], |sum, p| [0, 1, 2].map(|i| sum[i] + p[i]))
+                .map(|c| (c + 2) / 4);
+            u.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+            v.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+        }
+    }
+    yuv.extend(u);
+    yuv.extend(v);
+    yuv
+}
+
+impl Visualizer for Y4MVisualizer {
+    fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    fn check(&self, data: &[DrawData]) -> Result<(), VisualizerError> {
+        check_data(data)?;
+        self.animation.check()?;
+        if !self.animation.width().is_multiple_of(2) || !self.animation.height().is_multiple_of(2) {
+            return Err(VisualizerError::InvalidData(format!(
+                "Y4M frames need an even width and height for 4:2:0 chroma, got {}x{}",
+                self.animation.width(),
+                self.animation.height()
+            )));
+        }
+        Ok(())
+    }
+
+    fn render_to(
+        &self,
+        data: Vec<DrawData>,
+        writer: &mut dyn Write,
+    ) -> Result<RenderSummary, VisualizerError> {
+        self.check(&data)?;
+
+        let animation = self.animation.prepare(&data);
+        let frames = animation.frame_count();
+        let (numerator, denominator) = self.animation.frame_rate();
+
+        let mut writer = CountingWriter::new(writer);
+        writeln!(
+            writer,
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg XCOLORRANGE=LIMITED",
+            self.animation.width(),
+            self.animation.height(),
+            numerator,
+            denominator
+        )?;
+        render_in_order(
+            frames,
+            |frame| yuv420(&animation.render(frame)),
+            |yuv| {
+                writer.write_all(b"FRAME\n")?;
+                writer.write_all(&yuv)?;
+                Ok(())
+            },
+        )?;
+
+        Ok(RenderSummary {
+            output: None,
+            bytes: writer.count(),
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::{render_test_data, test_data};
+
+    #[test]
+    fn test_y4m_stream_layout() {
+        let animation = Animation::default()
+            .with_dimensions(16, 8)
+            .with_frames(2)
+            .with_fps(30);
+        let visualizer = Y4MVisualizer::default().with_animation(animation);
+
+        let (summary, stream) = render_test_data(&visualizer);
+        let header = b"YUV4MPEG2 W16 H8 F30:1 Ip A1:1 C420jpeg XCOLORRANGE=LIMITED\n";
+        assert!(stream.starts_with(header));
+        assert_eq!(stream.len(), header.len() + 2 * (6 + 16 * 8 * 3 / 2));
+        assert_eq!(summary.bytes, stream.len() as u64);
+
+        // White background in limited range
+        assert_eq!(stream[header.len() + 6], 235);
+
+        let odd =
+            Y4MVisualizer::default().with_animation(Animation::default().with_dimensions(15, 8));
+        assert!(odd.render_to(test_data(), &mut Vec::new()).is_err());
+    }
+}
@@ -35,6 +35,16 @@ impl FourierData {
         });
         result
     }
+
+    pub fn to_draw_data_vec(data: &[Self]) -> Vec<DrawData> {
+        data.iter()
+            .map(|d| DrawData {
+                frequency: d.frequency,
+                radius: d.radius,
+                angle: d.angle,
+            })
+            .collect()
+    }
 }
 
 #[tauri::command]
@@ -1,50 +1,31 @@
 use std::fs::File;
 use std::io::Write;
 
-use fourier_svg::DrawData;
+use fourier_svg::APNGVisualizer;
+use fourier_svg::Animation;
 use fourier_svg::GIFVisualizer;
 use fourier_svg::HTMLVisualizer;
+use fourier_svg::RenderSummary;
+use fourier_svg::Theme;
 use fourier_svg::Visualizer;
+use fourier_svg::WebPVisualizer;
+use fourier_svg::THEME_PRESETS;
 
 use super::drawing::FourierData;
 
-fn to_draw_data_vec(data: &[FourierData]) -> Vec<DrawData> {
-    data.iter()
-        .map(|d| DrawData {
-            frequency: d.frequency,
-            radius: d.radius,
-            angle: d.angle,
-        })
-        .collect()
-}
-
 #[tauri::command]
 pub async fn export_fourier_data(
     data: Vec<FourierData>,
     file_path: String,
     num_samples: usize,
 ) -> Result<(), String> {
-    use fourier_svg::FourierExport;
-
-    let draw_data = to_draw_data_vec(&data);
-
-    let export = FourierExport {
-        version: "1.0".to_string(),
-        metadata: fourier_svg::ExportMetadata {
-            svg_path: None,
-            sample_count: num_samples,
-            wave_count: draw_data.len(),
-            timestamp: chrono::Utc::now().timestamp(),
-        },
-        data: draw_data
-            .iter()
-            .map(|d| fourier_svg::FourierCoefficient {
-                frequency: d.frequency,
-                radius: d.radius,
-                angle: d.angle,
-            })
-            .collect(),
-    };
+    use fourier_svg::{ExportMetadata, FourierExport};
+
+    let draw_data = FourierData::to_draw_data_vec(&data);
+    let export = FourierExport::new(
+        &draw_data,
+        ExportMetadata::new(None, num_samples, draw_data.len()),
+    );
 
     let json_str = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
 
@@ -55,37 +36,96 @@ pub async fn export_fourier_data(
     Ok(())
 }
 
+/// Names of the built-in themes accepted by the exports
+#[tauri::command]
+pub fn list_themes() -> Vec<String> {
+    THEME_PRESETS.iter().map(|name| name.to_string()).collect()
+}
+
+/// A preset name or theme file, the classic theme when not given
+fn resolve_theme(theme: Option<String>) -> Result<Theme, String> {
+    match theme {
+        Some(theme) => Theme::resolve(&theme),
+        None => Ok(Theme::default()),
+    }
+}
+
+/// Frames of the animated exports, played over `duration` seconds
+fn animation(frames: usize, duration: f32, theme: Option<String>) -> Result<Animation, String> {
+    let delay_ms = (duration * 1000.0) / frames as f32;
+
+    Ok(Animation::default()
+        .with_dimensions(800, 600)
+        .with_frames(frames)
+        .with_delay_ms((delay_ms as u32).max(1))
+        .with_theme(resolve_theme(theme)?))
+}
+
 #[tauri::command]
 pub async fn export_as_gif(
     data: Vec<FourierData>,
     file_path: String,
     frames: usize,
     duration: f32,
-) -> Result<(), String> {
-    let draw_data = to_draw_data_vec(&data);
+    theme: Option<String>,
+) -> Result<RenderSummary, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
 
-    let delay = ((duration * 1000.0) / frames as f32) as u16 / 10;
+    let visualizer =
+        GIFVisualizer::new(file_path.clone()).with_animation(animation(frames, duration, theme)?);
 
-    let visualizer = GIFVisualizer::new(file_path.clone())
-        .with_dimensions(800, 600)
-        .with_frames(frames)
-        .with_delay(delay.max(1));
+    visualizer
+        .render(draw_data)
+        .map_err(|e| format!("Failed to create GIF: {}", e))
+}
 
-    if visualizer.render(draw_data) {
-        Ok(())
-    } else {
-        Err("Failed to create GIF".to_string())
-    }
+#[tauri::command]
+pub async fn export_as_apng(
+    data: Vec<FourierData>,
+    file_path: String,
+    frames: usize,
+    duration: f32,
+    theme: Option<String>,
+) -> Result<RenderSummary, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
+
+    let visualizer =
+        APNGVisualizer::new(file_path.clone()).with_animation(animation(frames, duration, theme)?);
+
+    visualizer
+        .render(draw_data)
+        .map_err(|e| format!("Failed to create APNG: {}", e))
 }
 
 #[tauri::command]
-pub async fn export_as_html(data: Vec<FourierData>, file_path: String) -> Result<(), String> {
-    let draw_data = to_draw_data_vec(&data);
-
-    let visualizer = HTMLVisualizer::new(file_path.clone());
-    if visualizer.render(draw_data) {
-        Ok(())
-    } else {
-        Err("Failed to create HTML".to_string())
-    }
+pub async fn export_as_webp(
+    data: Vec<FourierData>,
+    file_path: String,
+    frames: usize,
+    duration: f32,
+    theme: Option<String>,
+) -> Result<RenderSummary, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
+
+    let visualizer =
+        WebPVisualizer::new(file_path.clone()).with_animation(animation(frames, duration, theme)?);
+
+    visualizer
+        .render(draw_data)
+        .map_err(|e| format!("Failed to create WebP: {}", e))
+}
+
+#[tauri::command]
+pub async fn export_as_html(
+    data: Vec<FourierData>,
+    file_path: String,
+    theme: Option<String>,
+) -> Result<RenderSummary, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
+    let theme = resolve_theme(theme)?;
+
+    let visualizer = HTMLVisualizer::new(file_path.clone()).with_theme(theme);
+    visualizer
+        .render(draw_data)
+        .map_err(|e| format!("Failed to create HTML: {}", e))
 }
@@ -2,3 +2,4 @@ pub mod drawing;
 pub mod export_cmd;
 pub mod files;
 pub mod svg;
+pub mod symmetry;
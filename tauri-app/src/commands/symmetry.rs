@@ -0,0 +1,31 @@
+use fourier_svg::{
+    DetectedSymmetry, Symmetry, DEFAULT_MAX_SYMMETRY_ORDER, DEFAULT_SYMMETRY_THRESHOLD,
+};
+
+use super::drawing::FourierData;
+
+/// Symmetries of the data, with the same defaults as the CLI
+#[tauri::command]
+pub fn analyze_symmetry(
+    data: Vec<FourierData>,
+    max_order: Option<usize>,
+    threshold: Option<f32>,
+) -> Result<Vec<DetectedSymmetry>, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
+    Ok(fourier_svg::detect_symmetries(
+        &draw_data,
+        max_order.unwrap_or(DEFAULT_MAX_SYMMETRY_ORDER),
+        threshold.unwrap_or(DEFAULT_SYMMETRY_THRESHOLD),
+    ))
+}
+
+#[tauri::command]
+pub fn enforce_symmetry(
+    data: Vec<FourierData>,
+    symmetry: Symmetry,
+) -> Result<Vec<FourierData>, String> {
+    let draw_data = FourierData::to_draw_data_vec(&data);
+    let result = fourier_svg::enforce_symmetry(&draw_data, &symmetry);
+
+    Ok(FourierData::from_draw_data_vec(&result))
+}
@@ -14,10 +14,15 @@ fn run_tauri_app() {
             commands::svg::process_svg_path,
             commands::export_cmd::export_fourier_data,
             commands::export_cmd::export_as_gif,
+            commands::export_cmd::export_as_apng,
+            commands::export_cmd::export_as_webp,
             commands::export_cmd::export_as_html,
+            commands::export_cmd::list_themes,
             commands::files::save_canvas_as_png,
             commands::files::open_file_dialog,
             commands::files::save_file_dialog,
+            commands::symmetry::analyze_symmetry,
+            commands::symmetry::enforce_symmetry,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
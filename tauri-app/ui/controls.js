@@ -242,6 +242,51 @@ document.getElementById('showOriginalPath').addEventListener('change', (e) => {
     showOriginalPath = e.target.checked;
 });
 
+// Symmetry
+function describeSymmetry(s) {
+    if (s.kind === 'rotational') return s.order + '-fold rotational';
+    return 'Mirror (axis ' + (s.axis_angle * 180 / Math.PI).toFixed(1) + '\u00b0)';
+}
+
+document.getElementById('detectSymmetryBtn').addEventListener('click', async () => {
+    if (!fullFourierData) return;
+    try {
+        const detected = await tauriInvoke('analyze_symmetry', { data: fullFourierData });
+        const select = document.getElementById('symmetrySelect');
+        select.innerHTML = '';
+        for (const d of detected) {
+            const option = document.createElement('option');
+            option.value = JSON.stringify(d.symmetry);
+            option.textContent = describeSymmetry(d.symmetry) + ' \u2014 ' + (d.confidence * 100).toFixed(0) + '%';
+            select.appendChild(option);
+        }
+        for (let order = 2; order <= 8; order++) {
+            const option = document.createElement('option');
+            option.value = JSON.stringify({ kind: 'rotational', order });
+            option.textContent = 'Force ' + order + '-fold rotational';
+            select.appendChild(option);
+        }
+        document.getElementById('enforceSymmetryBtn').disabled = false;
+        updateStatus(detected.length > 0
+            ? 'Detected: ' + detected.map(d => describeSymmetry(d.symmetry)).join(', ')
+            : 'No symmetry detected');
+    } catch (err) {
+        updateStatus('Error detecting symmetry: ' + err);
+    }
+});
+
+document.getElementById('enforceSymmetryBtn').addEventListener('click', async () => {
+    const value = document.getElementById('symmetrySelect').value;
+    if (!fullFourierData || !value) return;
+    try {
+        const data = await tauriInvoke('enforce_symmetry', { data: fullFourierData, symmetry: JSON.parse(value) });
+        startVisualization(data);
+        updateStatus('Enforced ' + describeSymmetry(JSON.parse(value)));
+    } catch (err) {
+        updateStatus('Error enforcing symmetry: ' + err);
+    }
+});
+
 // Pause/Resume
 document.getElementById('pauseBtn').addEventListener('click', () => {
     is_paused = !is_paused;
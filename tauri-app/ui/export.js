@@ -27,20 +27,26 @@ document.getElementById('exportJsonBtn').addEventListener('click', async () => {
     }
 });
 
-// Export as GIF
-document.getElementById('exportGifBtn').addEventListener('click', async () => {
-    if (!fullFourierData) { updateStatus('No data to export'); return; }
-    try {
-        const filePath = await tauriDialogSave({ defaultName: 'fourier_animation.gif', filters: [{ name: 'GIF', extensions: ['gif'] }] });
-        if (filePath) {
-            updateStatus('Generating GIF...');
-            await tauriInvoke('export_as_gif', { data: fullFourierData, filePath, frames: parseInt(document.getElementById('gifFrames').value), duration: parseFloat(document.getElementById('gifDuration').value) });
-            updateStatus('GIF saved: ' + filePath);
+// Export as an animation: GIF, APNG or WebP
+function exportAnimation(buttonId, command, label, defaultName, extension) {
+    document.getElementById(buttonId).addEventListener('click', async () => {
+        if (!fullFourierData) { updateStatus('No data to export'); return; }
+        try {
+            const filePath = await tauriDialogSave({ defaultName, filters: [{ name: label, extensions: [extension] }] });
+            if (filePath) {
+                updateStatus('Generating ' + label + '...');
+                await tauriInvoke(command, { data: fullFourierData, filePath, frames: parseInt(document.getElementById('gifFrames').value), duration: parseFloat(document.getElementById('gifDuration').value), theme: document.getElementById('exportTheme').value });
+                updateStatus(label + ' saved: ' + filePath);
+            }
+        } catch (err) {
+            updateStatus('Error exporting ' + label + ': ' + err);
         }
-    } catch (err) {
-        updateStatus('Error exporting GIF: ' + err);
-    }
-});
+    });
+}
+
+exportAnimation('exportGifBtn', 'export_as_gif', 'GIF', 'fourier_animation.gif', 'gif');
+exportAnimation('exportApngBtn', 'export_as_apng', 'APNG', 'fourier_animation.png', 'png');
+exportAnimation('exportWebpBtn', 'export_as_webp', 'WebP', 'fourier_animation.webp', 'webp');
 
 // Export as HTML
 document.getElementById('exportHtmlBtn').addEventListener('click', async () => {
@@ -48,10 +54,27 @@ document.getElementById('exportHtmlBtn').addEventListener('click', async () => {
     try {
         const filePath = await tauriDialogSave({ defaultName: 'fourier_visualization.html', filters: [{ name: 'HTML', extensions: ['html'] }] });
         if (filePath) {
-            await tauriInvoke('export_as_html', { data: fullFourierData, filePath });
+            await tauriInvoke('export_as_html', { data: fullFourierData, filePath, theme: document.getElementById('exportTheme').value });
             updateStatus('HTML saved: ' + filePath);
         }
     } catch (err) {
         updateStatus('Error exporting HTML: ' + err);
     }
 });
+
+// Fill the theme selector with the built-in themes
+window.addEventListener('DOMContentLoaded', async () => {
+    try {
+        const themes = await tauriInvoke('list_themes', {});
+        const select = document.getElementById('exportTheme');
+        select.innerHTML = '';
+        for (const name of themes) {
+            const option = document.createElement('option');
+            option.value = name;
+            option.textContent = name;
+            select.appendChild(option);
+        }
+    } catch (err) {
+        updateStatus('Error loading themes: ' + err);
+    }
+});